@@ -0,0 +1,110 @@
+use crate::{interval::Interval, prelude::*};
+
+#[derive(Clone, Copy, Debug)]
+pub struct Aabb {
+    pub x: Interval,
+    pub y: Interval,
+    pub z: Interval,
+}
+
+impl Default for Aabb {
+    fn default() -> Self {
+        Self::EMPTY
+    }
+}
+
+impl Aabb {
+    pub const EMPTY: Aabb = Aabb::new(Interval::EMPTY, Interval::EMPTY, Interval::EMPTY);
+    pub const UNIVERSE: Aabb =
+        Aabb::new(Interval::UNIVERSE, Interval::UNIVERSE, Interval::UNIVERSE);
+
+    #[inline]
+    pub const fn new(x: Interval, y: Interval, z: Interval) -> Self {
+        Self { x, y, z }
+    }
+
+    #[inline]
+    pub fn from_points(a: Point3, b: Point3) -> Self {
+        Self::new(
+            Interval::new(a.x().min(b.x()), a.x().max(b.x())),
+            Interval::new(a.y().min(b.y()), a.y().max(b.y())),
+            Interval::new(a.z().min(b.z()), a.z().max(b.z())),
+        )
+    }
+
+    #[inline]
+    pub fn axis(&self, n: usize) -> Interval {
+        match n {
+            0 => self.x,
+            1 => self.y,
+            _ => self.z,
+        }
+    }
+
+    #[inline]
+    pub fn min(&self) -> Point3 {
+        Point3::new(self.x.min, self.y.min, self.z.min)
+    }
+
+    #[inline]
+    pub fn max(&self) -> Point3 {
+        Point3::new(self.x.max, self.y.max, self.z.max)
+    }
+
+    #[inline]
+    pub fn union(&self, other: &Aabb) -> Self {
+        Self::new(
+            self.x.union(&other.x),
+            self.y.union(&other.y),
+            self.z.union(&other.z),
+        )
+    }
+
+    #[inline]
+    pub fn intersection(&self, other: &Aabb) -> Self {
+        Self::new(
+            self.x.intersection(&other.x),
+            self.y.intersection(&other.y),
+            self.z.intersection(&other.z),
+        )
+    }
+
+    #[inline]
+    pub fn union_point(&self, point: &Point3) -> Self {
+        self.union(&Self::from_points(*point, *point))
+    }
+
+    #[inline]
+    pub fn corners(&self) -> [Point3; 8] {
+        let (a, b) = (self.min(), self.max());
+        [
+            Point3::new(a.x(), a.y(), a.z()),
+            Point3::new(b.x(), a.y(), a.z()),
+            Point3::new(a.x(), b.y(), a.z()),
+            Point3::new(b.x(), b.y(), a.z()),
+            Point3::new(a.x(), a.y(), b.z()),
+            Point3::new(b.x(), a.y(), b.z()),
+            Point3::new(a.x(), b.y(), b.z()),
+            Point3::new(b.x(), b.y(), b.z()),
+        ]
+    }
+
+    // Clips `t_interval` to the part of the ray inside the box using the slab
+    // method, or returns `None` if the ray misses it.
+    pub fn hit(&self, ray: &Ray, mut t_interval: Interval) -> Option<Interval> {
+        let origin = ray.origin();
+        let direction = ray.direction();
+        for axis in 0..3 {
+            let slab = self.axis(axis);
+            let inverse_direction = 1.0 / direction.e[axis];
+            let t0 = (slab.min - origin.e[axis]) * inverse_direction;
+            let t1 = (slab.max - origin.e[axis]) * inverse_direction;
+            let (t0, t1) = if t0 < t1 { (t0, t1) } else { (t1, t0) };
+            t_interval = Interval::new(t_interval.min.max(t0), t_interval.max.min(t1));
+            if t_interval.max <= t_interval.min {
+                return None;
+            }
+        }
+        return Some(t_interval);
+    }
+}
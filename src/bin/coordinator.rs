@@ -0,0 +1,73 @@
+// Splits a frame of the random spheres scene, built from the seed, into tiles
+// and hands them out to workers over TCP, then stitches their pixels back
+// together. Tiles of workers that disconnect or send nothing for the timeout,
+// 30 seconds by default, go back in the queue for the others.
+//
+//   coordinator <address> <output> [--tiles COLUMNSxROWS] [--width PIXELS]
+//               [--samples COUNT] [--seed SEED] [--timeout SECONDS]
+//
+// Start workers with `worker <address>` on this or other machines.
+use rust_raytracer::{
+    distributed::{coordinate, Job, DEFAULT_TIMEOUT},
+    scene::random_spheres,
+};
+use std::{error::Error, net::TcpListener, time::Duration};
+
+struct Options {
+    address: String,
+    output: String,
+    job: Job,
+    timeout: Duration,
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let options = parse_options()?;
+    let listener = TcpListener::bind(&options.address)?;
+    eprintln!(
+        "Listening on {} for {} tiles",
+        listener.local_addr()?,
+        options.job.tiles().count()
+    );
+    let frame = coordinate(
+        listener,
+        options.job,
+        |job| random_spheres(job.seed),
+        options.timeout,
+    )?;
+    frame.save(&options.output)?;
+    Ok(())
+}
+
+fn parse_options() -> Result<Options, Box<dyn Error>> {
+    let usage = "usage: coordinator <address> <output> [--tiles COLUMNSxROWS] \
+                 [--width PIXELS] [--samples COUNT] [--seed SEED] [--timeout SECONDS]";
+    let mut args = std::env::args().skip(1);
+    let address = args.next().ok_or(usage)?;
+    let output = args.next().ok_or(usage)?;
+    let mut options = Options {
+        address,
+        output,
+        job: Job {
+            seed: 0,
+            image_width: 1200,
+            samples_per_pixel: 500,
+            grid: [8, 8],
+        },
+        timeout: DEFAULT_TIMEOUT,
+    };
+    while let Some(flag) = args.next() {
+        let value = args.next().ok_or(usage)?;
+        match flag.as_str() {
+            "--tiles" => {
+                let (columns, rows) = value.split_once('x').ok_or(usage)?;
+                options.job.grid = [columns.parse()?, rows.parse()?];
+            }
+            "--width" => options.job.image_width = value.parse()?,
+            "--samples" => options.job.samples_per_pixel = value.parse()?,
+            "--seed" => options.job.seed = value.parse()?,
+            "--timeout" => options.timeout = Duration::try_from_secs_f32(value.parse()?)?,
+            _ => return Err(usage.into()),
+        }
+    }
+    Ok(options)
+}
@@ -0,0 +1,28 @@
+// Renders tiles of the random spheres scene for a coordinator, see
+// coordinator.rs.
+//
+//   worker <coordinator address>
+use rust_raytracer::{distributed::work, scene::random_spheres};
+use std::{error::Error, net::TcpStream, thread, time::Duration};
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let address = std::env::args()
+        .nth(1)
+        .ok_or("usage: worker <coordinator address>")?;
+    work(connect(&address)?, |job| random_spheres(job.seed))
+}
+
+// The coordinator may still be starting up, so keep trying for a while.
+fn connect(address: &str) -> Result<TcpStream, Box<dyn Error>> {
+    let mut attempts = 0;
+    loop {
+        match TcpStream::connect(address) {
+            Ok(stream) => return Ok(stream),
+            Err(error) if attempts >= 30 => return Err(error.into()),
+            Err(_) => {
+                attempts += 1;
+                thread::sleep(Duration::from_secs(1));
+            }
+        }
+    }
+}
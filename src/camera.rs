@@ -1,14 +1,51 @@
-use std::{error::Error, io::Write};
+use std::{
+    error::Error,
+    io::Write,
+    path::{Path, PathBuf},
+    rc::Rc,
+    time::{Duration, Instant},
+};
 
-use crate::{hittable::Hittable, interval::Interval, prelude::*, Color};
+use crate::{
+    checkpoint::{self, PixelStats},
+    film::Film,
+    filter::Filter,
+    hittable::Hittable,
+    image::Image,
+    interval::Interval,
+    medium::Fog,
+    prelude::*,
+    sampler::{Independent, PixelSample, SampleContext, Sampler},
+    spectrum::{rgb_to_spectrum, sample_wavelength, spectral_sample_to_rgb},
+};
 
 pub type RenderResult = Result<(), Box<dyn Error>>;
 
-#[derive(Debug)]
+// How often renders save their progress. A pass is a row of pixels in
+// scanline renders.
+#[derive(Clone, Copy, Debug)]
+pub enum SnapshotInterval {
+    Passes(usize),
+    Seconds(float),
+}
+
+// A window of the image to render, resolved to pixels once its size is known.
+#[derive(Clone, Copy, Debug)]
+enum Region {
+    Pixels([usize; 2], [usize; 2]),
+    Normalized([float; 2], [float; 2]),
+    Tile { index: [usize; 2], grid: [usize; 2] },
+}
+
+#[derive(Clone, Debug)]
 pub struct CameraBuilder {
     image_width: usize,
     aspect_ratio: float,
+    region: Option<Region>,
     samples_per_pixel: usize,
+    min_samples_per_pixel: usize,
+    noise_threshold: float,
+    sample_heatmap: Option<PathBuf>,
     max_depth: usize,
     look_from: Point3,
     look_at: Point3,
@@ -16,6 +53,16 @@ pub struct CameraBuilder {
     vfov: float,
     focal_distance: float,
     defocus_angle: float,
+    shutter: Interval,
+    spectral: bool,
+    fog: Option<Fog>,
+    sampler: Rc<dyn Sampler>,
+    filter: Filter,
+    progressive: bool,
+    pass_limit: Option<usize>,
+    snapshots: Option<(PathBuf, SnapshotInterval)>,
+    checkpoint: Option<(PathBuf, SnapshotInterval)>,
+    scene_id: u64,
 }
 
 impl Default for CameraBuilder {
@@ -29,8 +76,22 @@ impl Default for CameraBuilder {
             focal_distance: 10.0,
             max_depth: 10,
             samples_per_pixel: 10,
+            min_samples_per_pixel: 16,
+            noise_threshold: 0.0,
+            sample_heatmap: None,
             image_width: 100,
             aspect_ratio: 1.0,
+            region: None,
+            shutter: Interval::new(0.0, 0.0),
+            spectral: false,
+            fog: None,
+            sampler: Rc::new(Independent::default()),
+            filter: Filter::default(),
+            progressive: false,
+            pass_limit: None,
+            snapshots: None,
+            checkpoint: None,
+            scene_id: 0,
         }
     }
 }
@@ -54,11 +115,127 @@ impl CameraBuilder {
     builder_fn!(defocus_angle: float, with_defocus_angle);
     builder_fn!(max_depth: usize, with_max_depth);
     builder_fn!(samples_per_pixel: usize, with_samples_per_pixel);
+    builder_fn!(min_samples_per_pixel: usize, with_min_samples_per_pixel);
     builder_fn!(image_width: usize, with_image_width);
     builder_fn!(aspect_ratio: float, with_aspect_ratio);
+    builder_fn!(shutter: Interval, with_shutter);
+    builder_fn!(spectral: bool, with_spectral);
+    builder_fn!(filter: Filter, with_filter);
+    builder_fn!(progressive: bool, with_progressive);
+
+    // Renders only the pixels from `min` up to but excluding `max`, as
+    // [x, y], e.g. a detail for look development. The output image is just
+    // that window.
+    #[inline]
+    pub fn with_crop_window(mut self, min: [usize; 2], max: [usize; 2]) -> Self {
+        self.region = Some(Region::Pixels(min, max));
+        self
+    }
+
+    // The same, with corners from 0 to 1 across the image.
+    #[inline]
+    pub fn with_normalized_crop_window(mut self, min: [float; 2], max: [float; 2]) -> Self {
+        self.region = Some(Region::Normalized(min, max));
+        self
+    }
+
+    // Renders tile `index`, as [column, row], of the image split into a grid
+    // of `grid` columns by rows. Tiles are sampled and filtered just as in a
+    // whole render, so pasting each at its `Camera::crop_window` gives the
+    // same image.
+    #[inline]
+    pub fn with_tile(mut self, index: [usize; 2], grid: [usize; 2]) -> Self {
+        self.region = Some(Region::Tile { index, grid });
+        self
+    }
+
+    #[inline]
+    pub fn with_fog(mut self, fog: Fog) -> Self {
+        self.fog = Some(fog);
+        self
+    }
+
+    // Enables adaptive sampling: pixels stop taking samples once the standard
+    // error of their displayed value falls below `threshold`, e.g. 0.01, after
+    // at least the minimum and at most `samples_per_pixel` samples. Raise the
+    // minimum for scenes where rare paths carry a lot of light, since a pixel
+    // that has not seen them yet looks converged.
+    #[inline]
+    pub fn with_noise_threshold(mut self, threshold: float) -> Self {
+        self.noise_threshold = threshold;
+        self
+    }
+
+    // Saves an image of how many samples each pixel took after rendering,
+    // from blue for few through green to red for the maximum.
+    #[inline]
+    pub fn with_sample_heatmap(mut self, path: impl Into<PathBuf>) -> Self {
+        self.sample_heatmap = Some(path.into());
+        self
+    }
+
+    // Saves the image so far to `path` as the render goes, so that it can be
+    // stopped early. The file is replaced whole each time. Best used with
+    // progressive renders, where every snapshot covers the whole frame.
+    #[inline]
+    pub fn with_snapshots(mut self, path: impl Into<PathBuf>, interval: SnapshotInterval) -> Self {
+        self.snapshots = Some((path.into(), interval));
+        self
+    }
+
+    // Saves the render's state to `path` as it goes, and resumes from it if
+    // the file already exists, giving the same image as an uninterrupted
+    // render. The world can't be inspected, so `scene_id` must identify it,
+    // e.g. a `checkpoint::StableHasher` hash of the seed, meshes and images it
+    // is built from. Rendering fails if the checkpoint was made with another
+    // scene id or camera setup.
+    #[inline]
+    pub fn with_checkpoint(
+        mut self,
+        path: impl Into<PathBuf>,
+        interval: SnapshotInterval,
+        scene_id: u64,
+    ) -> Self {
+        self.checkpoint = Some((path.into(), interval));
+        self.scene_id = scene_id;
+        self
+    }
+
+    // Stops a progressive render after this many passes, saving a checkpoint
+    // if there is one so that a later run can carry on, e.g. to fit a long
+    // render into several shorter jobs. Scanline renders have no passes to
+    // stop after.
+    #[inline]
+    pub fn with_pass_limit(mut self, passes: usize) -> Self {
+        self.pass_limit = Some(passes);
+        self
+    }
+
+    #[inline]
+    pub fn with_sampler(mut self, sampler: impl Sampler + 'static) -> Self {
+        self.sampler = Rc::new(sampler);
+        self
+    }
 
     #[inline]
     pub fn build(self) -> Camera {
+        assert!(
+            self.samples_per_pixel >= 1,
+            "cameras must take at least one sample per pixel"
+        );
+        assert!(
+            self.pass_limit != Some(0),
+            "pass limits must allow at least one pass"
+        );
+        let intervals = [&self.snapshots, &self.checkpoint];
+        for (_, interval) in intervals.into_iter().flatten() {
+            if let SnapshotInterval::Seconds(seconds) = *interval {
+                assert!(
+                    Duration::try_from_secs_f32(seconds).is_ok(),
+                    "save intervals must be a finite, non-negative number of seconds"
+                );
+            }
+        }
         let image_height = (((self.image_width as float) / self.aspect_ratio) as usize).max(1);
 
         let center = self.look_from;
@@ -81,6 +258,42 @@ impl CameraBuilder {
             center - (self.focal_distance * w) - (viewport_u + viewport_v) / 2.0;
         let top_left_pixel = viewport_top_left + 0.5 * (pixel_du + pixel_dv);
 
+        let size = [self.image_width, image_height];
+        let [min, max] = match self.region {
+            None => [[0, 0], size],
+            Some(Region::Pixels(min, max)) => [min, max],
+            Some(Region::Normalized(min, max)) => {
+                assert!(
+                    (0..2).all(|a| 0.0 <= min[a] && min[a] < max[a] && max[a] <= 1.0),
+                    "normalized crop windows must lie within [0, 1] and not be empty"
+                );
+                let to_pixels =
+                    |p: [float; 2]| [0, 1].map(|a| (p[a] * size[a] as float).ceil() as usize);
+                [to_pixels(min), to_pixels(max)]
+            }
+            Some(Region::Tile { index, grid }) => {
+                assert!(
+                    (0..2).all(|a| 1 <= grid[a] && grid[a] <= size[a]),
+                    "tile grids must have between one and as many tiles as pixels along each axis"
+                );
+                assert!(
+                    (0..2).all(|a| index[a] < grid[a]),
+                    "tile {:?} is outside the {:?} grid",
+                    index,
+                    grid
+                );
+                let bound = |k: [usize; 2]| [0, 1].map(|a| k[a] * size[a] / grid[a]);
+                [bound(index), bound(index.map(|k| k + 1))]
+            }
+        };
+        assert!(
+            (0..2).all(|a| min[a] < max[a] && max[a] <= size[a]),
+            "crop window {:?} to {:?} must be non-empty and within the {:?} image",
+            min,
+            max,
+            size
+        );
+
         let defocus = (self.defocus_angle > 0.0).then(|| {
             let defocus_radius =
                 self.focal_distance * (self.defocus_angle / 2.0).to_radians().tan();
@@ -90,13 +303,27 @@ impl CameraBuilder {
         Camera {
             image_width: self.image_width,
             samples_pex_pixel: self.samples_per_pixel,
+            min_samples_per_pixel: self.min_samples_per_pixel.clamp(1, self.samples_per_pixel),
+            noise_threshold: self.noise_threshold,
+            sample_heatmap: self.sample_heatmap,
             max_depth: self.max_depth,
             image_height,
+            crop_window: [min, max],
             center,
             top_left_pixel,
             pixel_du,
             pixel_dv,
             defocus,
+            shutter: self.shutter,
+            spectral: self.spectral,
+            fog: self.fog,
+            sampler: self.sampler,
+            filter: self.filter,
+            progressive: self.progressive,
+            pass_limit: self.pass_limit,
+            snapshots: self.snapshots,
+            checkpoint: self.checkpoint,
+            scene_id: self.scene_id,
         }
     }
 }
@@ -105,77 +332,421 @@ impl CameraBuilder {
 pub struct Camera {
     image_width: usize,
     image_height: usize,
+    crop_window: [[usize; 2]; 2],
     samples_pex_pixel: usize,
+    min_samples_per_pixel: usize,
+    noise_threshold: float,
+    sample_heatmap: Option<PathBuf>,
     max_depth: usize,
     center: Point3,
     top_left_pixel: Point3,
     pixel_du: Vec3,
     pixel_dv: Vec3,
     defocus: Option<[Vec3; 2]>,
+    shutter: Interval,
+    spectral: bool,
+    fog: Option<Fog>,
+    sampler: Rc<dyn Sampler>,
+    filter: Filter,
+    progressive: bool,
+    pass_limit: Option<usize>,
+    snapshots: Option<(PathBuf, SnapshotInterval)>,
+    checkpoint: Option<(PathBuf, SnapshotInterval)>,
+    scene_id: u64,
 }
 
 impl Camera {
+    // Renders the image and writes it to stdout as a PPM.
     pub fn render(&self, world: &impl Hittable) -> RenderResult {
-        let log = &mut std::io::stderr();
         let output = &mut std::io::stdout();
-
-        write_ppm_header(output, self.image_width, self.image_height)?;
-        for j in 0..self.image_height {
-            let _ = writeln!(log, "Scanline progress: {}/{}", j, self.image_height);
-            for i in 0..self.image_width {
-                let color = (0..self.samples_pex_pixel)
-                    .map(|_| ray_color(&self.get_ray(i, j), world, self.max_depth))
-                    .sum::<Color>()
-                    / (self.samples_pex_pixel as float);
-                write_ppm_pixel(output, color.gamma_corrected())?;
+        let image = self.render_image(world)?;
+        write_ppm_header(output, image.width(), image.height())?;
+        for j in 0..image.height() {
+            for i in 0..image.width() {
+                write_ppm_pixel(output, image.pixel(i, j).gamma_corrected())?;
             }
         }
-        let _ = writeln!(log, "Done");
         output.flush()?;
         Ok(())
     }
 
-    fn get_ray(&self, i: usize, j: usize) -> Ray {
-        let pixel_center =
-            self.top_left_pixel + (i as float) * self.pixel_du + (j as float) * self.pixel_dv;
-        let pixel_sample = pixel_center + self.get_pixel_sample();
-        let ray_origin = self.center + self.get_defocus();
-        let ray_direction = pixel_sample - ray_origin;
-        return Ray::new(ray_origin, ray_direction);
+    // Renders the crop window into a linear image. By default pixels are
+    // rendered one after the other, each with all of its samples, while
+    // progressive renders take one sample of every pixel per pass.
+    pub fn render_image(&self, world: &impl Hittable) -> Result<Image, Box<dyn Error>> {
+        let log = &mut std::io::stderr();
+
+        // Pixels within the filter radius around the crop window are sampled
+        // too, since their samples spill into it.
+        let [min, max] = self.crop_window;
+        // Filters include their edge, so a sample right on the edge of the
+        // pixel past the window still reaches into it.
+        let margin = (self.filter.radius() + 0.5).floor() as usize;
+        let start = [min[0].saturating_sub(margin), min[1].saturating_sub(margin)];
+        let end = [
+            (max[0] + margin).min(self.image_width),
+            (max[1] + margin).min(self.image_height),
+        ];
+        let [width, height] = [end[0] - start[0], end[1] - start[1]];
+        let index = |i: usize, j: usize| (j - start[1]) * width + (i - start[0]);
+
+        let mut film = Film::new(width, height, self.filter).with_origin(start);
+        let mut pixels = vec![PixelStats::default(); width * height];
+        let mut progress = Progress::new(self);
+        if let Some((path, _)) = &self.checkpoint {
+            if checkpoint::load(path, progress.fingerprint, &mut film, &mut pixels)? {
+                let _ = writeln!(log, "Resuming from checkpoint {}", path.display());
+            }
+        }
+
+        if self.progressive {
+            // Checkpoints are saved between passes, when all the pixels still
+            // being sampled have taken the same number of samples.
+            let first_pass = pixels.iter().map(|stats| stats.count).max().unwrap_or(0) + 1;
+            let last_pass = match self.pass_limit {
+                Some(limit) => self.samples_pex_pixel.min(first_pass + limit - 1),
+                None => self.samples_pex_pixel,
+            };
+            for pass in first_pass..=last_pass {
+                for j in start[1]..end[1] {
+                    for i in start[0]..end[0] {
+                        let stats = &mut pixels[index(i, j)];
+                        if !stats.converged {
+                            self.take_sample(i, j, world, &mut film, stats);
+                        }
+                    }
+                }
+                let _ = writeln!(log, "Pass progress: {}/{}", pass, self.samples_pex_pixel);
+                let done = pixels.iter().all(|stats| stats.converged);
+                progress.update(pass, done || pass == last_pass, &film, &pixels)?;
+                if done {
+                    break;
+                }
+            }
+        } else {
+            for j in start[1]..end[1] {
+                let row = j - start[1];
+                let _ = writeln!(log, "Scanline progress: {}/{}", row, height);
+                for i in start[0]..end[0] {
+                    let stats = &mut pixels[index(i, j)];
+                    while !stats.converged {
+                        self.take_sample(i, j, world, &mut film, stats);
+                    }
+                }
+                progress.update(row + 1, j + 1 == end[1], &film, &pixels)?;
+            }
+        }
+        let total: usize = pixels.iter().map(|stats| stats.count).sum();
+        let _ = writeln!(
+            log,
+            "Done, {:.1} samples per pixel on average",
+            total as float / pixels.len() as float
+        );
+
+        let image = window_image(&film, self.crop_window);
+        if let Some(path) = &self.sample_heatmap {
+            let colors = (min[1]..max[1])
+                .flat_map(|j| (min[0]..max[0]).map(move |i| (i, j)))
+                .map(|(i, j)| pixels[index(i, j)].count as float / self.samples_pex_pixel as float)
+                .map(heatmap_color)
+                .collect();
+            Image::new(image.width(), image.height(), colors).save_raw(path)?;
+        }
+        Ok(image)
+    }
+
+    #[inline]
+    pub fn image_width(&self) -> usize {
+        self.image_width
     }
 
-    fn get_pixel_sample(&self) -> Vec3 {
-        let px = -0.5 + rand_norm();
-        let py = -0.5 + rand_norm();
-        (px * self.pixel_du) + (py * self.pixel_dv)
+    #[inline]
+    pub fn image_height(&self) -> usize {
+        self.image_height
+    }
+
+    // The pixels rendered, from the first corner up to but excluding the
+    // second, as [x, y]. The whole image unless cropped or tiled.
+    #[inline]
+    pub fn crop_window(&self) -> [[usize; 2]; 2] {
+        self.crop_window
     }
 
-    fn get_defocus(&self) -> Vec3 {
+    // Everything that affects the rendered image, but not where it is saved or
+    // how many passes a run takes.
+    fn fingerprint(&self) -> u64 {
+        checkpoint::fingerprint(&[
+            &self.image_width,
+            &self.image_height,
+            &self.crop_window,
+            &self.samples_pex_pixel,
+            &self.min_samples_per_pixel,
+            &self.noise_threshold,
+            &self.max_depth,
+            &self.center,
+            &self.top_left_pixel,
+            &self.pixel_du,
+            &self.pixel_dv,
+            &self.defocus,
+            &self.shutter,
+            &self.spectral,
+            &self.fog,
+            &self.sampler,
+            &self.filter,
+            &self.progressive,
+            &self.scene_id,
+        ])
+    }
+
+    // Takes the next sample of a pixel into the film. The pixel is done after
+    // `samples_per_pixel` samples, or earlier when adaptive sampling finds it
+    // converged. Convergence is checked every `min_samples_per_pixel` samples
+    // on a running variance of each channel of the pixel's own samples.
+    fn take_sample(
+        &self,
+        i: usize,
+        j: usize,
+        world: &impl Hittable,
+        film: &mut Film,
+        stats: &mut PixelStats,
+    ) {
+        let sample = PixelSample {
+            pixel: [i, j],
+            index: stats.count,
+            count: self.samples_pex_pixel,
+        };
+        let mut sampler = SampleContext::new(&*self.sampler, sample);
+        let (color, position) = self.sample_color(i, j, world, &mut sampler);
+        film.add_sample(position, color);
+
+        stats.count += 1;
+        let count = stats.count;
+        let delta = color - stats.mean;
+        stats.mean += delta / count as float;
+        stats.squared_deviations += delta * (color - stats.mean);
+        if count >= self.samples_pex_pixel {
+            stats.converged = true;
+        } else if self.noise_threshold > 0.0
+            && count >= self.min_samples_per_pixel
+            && count.is_multiple_of(self.min_samples_per_pixel)
+        {
+            // The standard error of the noisiest channel as displayed, after
+            // the square root gamma curve.
+            let variance = stats.squared_deviations / (count - 1).max(1) as float;
+            let error = (0..3)
+                .map(|c| {
+                    let standard_error = (variance.e[c] / count as float).sqrt();
+                    standard_error / (2.0 * stats.mean.e[c].max(1e-4).sqrt())
+                })
+                .fold(0.0, float::max);
+            stats.converged = error < self.noise_threshold;
+        }
+    }
+
+    // Traces a sample at a random position in the pixel, returning its color
+    // and the position in pixels from the top left corner of the image. In
+    // spectral mode every sample traces a single wavelength, and its radiance
+    // is converted back to RGB here.
+    fn sample_color(
+        &self,
+        i: usize,
+        j: usize,
+        world: &impl Hittable,
+        sampler: &mut SampleContext,
+    ) -> (Color, [float; 2]) {
+        let [dx, dy] = sampler.next_2d();
+        let position = [i as float + dx, j as float + dy];
+        let ray = self.get_ray(position, sampler);
+        let color = self.ray_color(&ray, world, self.max_depth, sampler);
+        let color = match ray.wavelength() {
+            Some(wavelength) => spectral_sample_to_rgb(color.x(), wavelength),
+            None => color,
+        };
+        return (color, position);
+    }
+
+    fn get_ray(&self, [x, y]: [float; 2], sampler: &mut SampleContext) -> Ray {
+        let pixel_sample =
+            self.top_left_pixel + (x - 0.5) * self.pixel_du + (y - 0.5) * self.pixel_dv;
+        let ray_origin = self.center + self.get_defocus(sampler);
+        let ray_direction = pixel_sample - ray_origin;
+        let ray_time = self.shutter.lerp(sampler.next_1d());
+        let ray = Ray::new_with_time(ray_origin, ray_direction, ray_time);
+        if self.spectral {
+            return ray.with_wavelength(sample_wavelength(sampler.next_1d()));
+        }
+        return ray;
+    }
+
+    // The lens sample is drawn even without defocus, so that the dimensions
+    // after it stay put.
+    fn get_defocus(&self, sampler: &mut SampleContext) -> Vec3 {
+        let p = Vec3::random_in_unit_disk(sampler);
         self.defocus
-            .map(|[u, v]| {
-                let p = Vec3::random_in_unit_disk();
-                return (p.x() * u) + (p.y() * v);
-            })
+            .map(|[u, v]| (p.x() * u) + (p.y() * v))
             .unwrap_or_default()
     }
+
+    fn ray_color(
+        &self,
+        ray: &Ray,
+        world: &impl Hittable,
+        max_depth: usize,
+        sampler: &mut SampleContext,
+    ) -> Color {
+        if max_depth == 0 {
+            return Color::ZERO;
+        }
+        sampler.start_bounce(self.max_depth - max_depth);
+
+        let surface = world.hit(ray, Interval::new(0.001, float::INFINITY));
+
+        // Media and fog collide with the ray before it reaches the next surface
+        // with the probability of it being absorbed or scattered on the way, so
+        // surviving rays need no extra transmittance weight. The nearest
+        // collision or surface is where the ray interacts.
+        let t_max = surface.as_ref().map_or(float::INFINITY, |hit| hit.t);
+        let collision = world.sample_medium(ray, Interval::new(0.001, t_max), sampler);
+        if let Some(fog) = &self.fog {
+            let t_max = collision.as_ref().map_or(t_max, |hit| hit.t);
+            if let Some((albedo, scattered)) =
+                fog.scatter(ray, &world.bounding_box(), t_max, sampler)
+            {
+                return project_to_ray(albedo, ray)
+                    * self.ray_color(&scattered, world, max_depth - 1, sampler);
+            }
+        }
+        let hit = collision.or(surface);
+
+        if let Some(hit) = hit {
+            let emitted = project_to_ray(hit.material.emitted(ray, &hit), ray);
+            return emitted
+                + hit
+                    .material
+                    .scatter(ray, &hit, sampler)
+                    .map(|(attenuation, scatter)| {
+                        project_to_ray(attenuation, ray)
+                            * self.ray_color(&scatter, world, max_depth - 1, sampler)
+                    })
+                    .unwrap_or(Color::ZERO);
+        }
+
+        let direction = ray.direction().unit();
+        let a = 0.5 * (direction.y() + 1.0);
+        let sky = (1.0 - a) * Color::new(1.0, 1.0, 1.0) + a * Color::new(0.5, 0.7, 1.0);
+        return project_to_ray(sky, ray);
+    }
+}
+
+// Materials and lights are authored in RGB; for rays carrying a wavelength the
+// color is upsampled to a spectrum and evaluated there, in all channels.
+fn project_to_ray(color: Color, ray: &Ray) -> Color {
+    return match ray.wavelength() {
+        Some(wavelength) => Color::uniform(rgb_to_spectrum(&color, wavelength)),
+        None => color,
+    };
+}
+
+// Saves snapshots and checkpoints when they are due, and once more when the
+// render is done.
+struct Progress<'a> {
+    fingerprint: u64,
+    crop_window: [[usize; 2]; 2],
+    snapshots: Option<(&'a Path, Schedule)>,
+    checkpoint: Option<(&'a Path, Schedule)>,
+}
+
+impl<'a> Progress<'a> {
+    fn new(camera: &'a Camera) -> Self {
+        let schedule = |(path, interval): &'a (PathBuf, SnapshotInterval)| {
+            (path.as_path(), Schedule::new(*interval))
+        };
+        Self {
+            fingerprint: camera.fingerprint(),
+            crop_window: camera.crop_window,
+            snapshots: camera.snapshots.as_ref().map(schedule),
+            checkpoint: camera.checkpoint.as_ref().map(schedule),
+        }
+    }
+
+    fn update(
+        &mut self,
+        pass: usize,
+        done: bool,
+        film: &Film,
+        pixels: &[PixelStats],
+    ) -> RenderResult {
+        if let Some((path, schedule)) = &mut self.snapshots {
+            if schedule.due(pass) || done {
+                save_snapshot(&window_image(film, self.crop_window), path)?;
+            }
+        }
+        if let Some((path, schedule)) = &mut self.checkpoint {
+            if schedule.due(pass) || done {
+                checkpoint::save(path, self.fingerprint, film, pixels)?;
+            }
+        }
+        Ok(())
+    }
 }
 
-fn ray_color(ray: &Ray, world: &impl Hittable, max_depth: usize) -> Color {
-    if max_depth == 0 {
-        return Color::ZERO;
+struct Schedule {
+    interval: SnapshotInterval,
+    last: Instant,
+}
+
+impl Schedule {
+    fn new(interval: SnapshotInterval) -> Self {
+        Self {
+            interval,
+            last: Instant::now(),
+        }
     }
 
-    if let Some(hit) = world.hit(ray, Interval::new(0.001, float::INFINITY)) {
-        return hit
-            .material
-            .scatter(ray, &hit)
-            .map(|(attenuation, scatter)| attenuation * ray_color(&scatter, world, max_depth - 1))
-            .unwrap_or(Color::ZERO);
+    fn due(&mut self, pass: usize) -> bool {
+        let due = match self.interval {
+            SnapshotInterval::Passes(passes) => pass.is_multiple_of(passes.max(1)),
+            SnapshotInterval::Seconds(seconds) => {
+                self.last.elapsed() >= Duration::from_secs_f32(seconds)
+            }
+        };
+        if due {
+            self.last = Instant::now();
+        }
+        return due;
     }
+}
+
+// The pixels of the film within the crop window, leaving out the margin.
+fn window_image(film: &Film, [min, max]: [[usize; 2]; 2]) -> Image {
+    let pixels = (min[1]..max[1])
+        .flat_map(|j| (min[0]..max[0]).map(move |i| film.pixel(i, j)))
+        .collect();
+    return Image::new(max[0] - min[0], max[1] - min[1], pixels);
+}
+
+// Writes next to the snapshot first and then moves it over, so that the file
+// is never left half written.
+fn save_snapshot(image: &Image, path: &Path) -> RenderResult {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default();
+    let partial = path.with_extension(format!("partial.{}", extension));
+    image.save(&partial)?;
+    std::fs::rename(&partial, path)?;
+    Ok(())
+}
 
-    let direction = ray.direction().unit();
-    let a = 0.5 * (direction.y() + 1.0);
-    return (1.0 - a) * Color::new(1.0, 1.0, 1.0) + a * Color::new(0.5, 0.7, 1.0);
+// Blue through green to red as `t` goes from 0 to 1.
+fn heatmap_color(t: float) -> Color {
+    let t = t.clamp(0.0, 1.0);
+    if t < 0.5 {
+        let t = 2.0 * t;
+        return Color::new(0.0, t, 1.0 - t);
+    }
+    let t = 2.0 * t - 1.0;
+    return Color::new(t, 1.0 - t, 0.0);
 }
 
 fn write_ppm_header(output: &mut impl Write, width: usize, height: usize) -> RenderResult {
@@ -192,3 +763,95 @@ fn write_ppm_pixel(output: &mut impl Write, color: Color) -> RenderResult {
     writeln!(output, "{} {} {}", r, g, b)?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        hittable_list::HittableList, material::Lambertian, scene::random_spheres, sphere::Sphere,
+    };
+
+    #[test]
+    fn crop_window_matches_the_full_render() {
+        let (world, camera) = random_spheres(3);
+        let camera = camera
+            .with_image_width(40)
+            .with_samples_per_pixel(4)
+            .with_filter(Filter::mitchell(2.0));
+        let full = camera.clone().build().render_image(&world).unwrap();
+
+        // Corners that fall between pixels, so that the window has to be
+        // rounded to them.
+        let cropped = camera
+            .with_normalized_crop_window([0.21, 0.33], [0.67, 0.9])
+            .build();
+        let [min, max] = cropped.crop_window();
+        let image = cropped.render_image(&world).unwrap();
+        assert_eq!(
+            [image.width(), image.height()],
+            [max[0] - min[0], max[1] - min[1]]
+        );
+        for j in 0..image.height() {
+            for i in 0..image.width() {
+                assert_eq!(image.pixel(i, j).e, full.pixel(min[0] + i, min[1] + j).e);
+            }
+        }
+    }
+
+    #[test]
+    fn adaptive_sampling_spends_samples_on_edges() {
+        // A black sphere against the sky, both flat apart from the edge.
+        let mut world = HittableList::new();
+        let black = Rc::new(Lambertian::new(Color::ZERO));
+        world.add(Box::new(Sphere::new(Point3::NEG_Z, 0.5, black)));
+        let camera = CameraBuilder::default()
+            .with_look_from(Point3::ZERO)
+            .with_look_at(Point3::NEG_Z)
+            .with_image_width(32)
+            .with_samples_per_pixel(64)
+            .with_min_samples_per_pixel(8)
+            .with_noise_threshold(0.01)
+            .build();
+
+        // Samples every pixel of the middle row until it converges.
+        let (width, j) = (camera.image_width(), camera.image_height() / 2);
+        let mut film = Film::new(width, camera.image_height(), camera.filter);
+        let row: Vec<PixelStats> = (0..width)
+            .map(|i| {
+                let mut stats = PixelStats::default();
+                while !stats.converged {
+                    camera.take_sample(i, j, &world, &mut film, &mut stats);
+                }
+                stats
+            })
+            .collect();
+
+        let sky = &row[0];
+        let middle = &row[width / 2];
+        assert!(middle.mean.lenght() == 0.0 && sky.mean.lenght() > 0.5);
+        assert_eq!(sky.count, 8);
+        assert_eq!(middle.count, 8);
+        // The pixel where the row first reaches the sphere is partly covered.
+        let edge = row.iter().position(|stats| stats.mean.b() < 0.5).unwrap();
+        assert!(edge > 0 && edge < width / 2);
+        assert!(
+            row[edge - 1].count.max(row[edge].count) > 8,
+            "{:?}",
+            &row[edge - 1..=edge]
+        );
+    }
+
+    #[test]
+    fn snapshots_are_due_every_few_passes() {
+        let due_passes = |passes: usize| {
+            let mut schedule = Schedule::new(SnapshotInterval::Passes(passes));
+            (1..=10)
+                .filter(|&pass| schedule.due(pass))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(due_passes(3), [3, 6, 9]);
+        assert_eq!(due_passes(1), (1..=10).collect::<Vec<_>>());
+        // Zero is taken as every pass rather than never.
+        assert_eq!(due_passes(0), due_passes(1));
+    }
+}
@@ -0,0 +1,168 @@
+use std::{
+    error::Error,
+    fmt::{self, Debug, Write as _},
+    hash::Hasher,
+    path::Path,
+};
+
+use crate::{film::Film, prelude::*};
+
+pub type CheckpointResult<T> = Result<T, Box<dyn Error>>;
+
+const VERSION: u32 = 1;
+
+// Bytes per pixel: the film's sum and weight, then the sample count, mean,
+// squared deviations and whether it converged.
+const PIXEL_SIZE: usize = 16 + 4 + 12 + 12 + 1;
+
+// Running statistics of the samples taken in a pixel, for adaptive sampling.
+// Samplers are deterministic per pixel and sample index, so the count is also
+// where the pixel's sample sequence continues from.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PixelStats {
+    pub count: usize,
+    pub mean: Color,
+    pub squared_deviations: Color,
+    pub converged: bool,
+}
+
+// Identifies a camera setup by hashing its debug output, so that a checkpoint
+// is only resumed by the render that made it.
+pub fn fingerprint(parts: &[&dyn Debug]) -> u64 {
+    let mut hasher = StableHasher::default();
+    for part in parts {
+        let _ = write!(hasher, "{:?};", part);
+    }
+    return hasher.finish();
+}
+
+// 64-bit FNV-1a, which unlike the std hashers is stable across releases, for
+// hashing a scene's data into the id its checkpoints are made with.
+#[derive(Clone, Copy, Debug)]
+pub struct StableHasher(u64);
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        Self(0xcbf29ce484222325)
+    }
+}
+
+impl Hasher for StableHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ byte as u64).wrapping_mul(0x100000001b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+impl fmt::Write for StableHasher {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.write(s.as_bytes());
+        Ok(())
+    }
+}
+
+// A text header "CHECKPOINT version width height fingerprint", followed by the
+// state of each pixel in little endian. The file is written next to `path`
+// and then moved over it, so a render killed while saving keeps the previous
+// checkpoint.
+pub fn save(
+    path: &Path,
+    fingerprint: u64,
+    film: &Film,
+    pixels: &[PixelStats],
+) -> CheckpointResult<()> {
+    let header = format!(
+        "CHECKPOINT {} {} {} {:016x}\n",
+        VERSION,
+        film.width(),
+        film.height(),
+        fingerprint
+    );
+    let mut data = Vec::with_capacity(header.len() + pixels.len() * PIXEL_SIZE);
+    data.extend_from_slice(header.as_bytes());
+
+    let (sums, weights) = film.accumulators();
+    for ((sum, weight), stats) in sums.iter().zip(weights).zip(pixels) {
+        let floats = sum.e.iter().chain([weight]);
+        for value in floats {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.extend_from_slice(&(stats.count as u32).to_le_bytes());
+        for value in stats.mean.e.iter().chain(&stats.squared_deviations.e) {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.push(stats.converged as u8);
+    }
+
+    let partial = path.with_extension("partial");
+    std::fs::write(&partial, data)?;
+    std::fs::rename(&partial, path)?;
+    Ok(())
+}
+
+// Restores the state saved at `path` into a fresh film and pixels, returning
+// false if there is no checkpoint there yet. Fails if the checkpoint was made
+// with a different fingerprint or image size.
+pub fn load(
+    path: &Path,
+    fingerprint: u64,
+    film: &mut Film,
+    pixels: &mut [PixelStats],
+) -> CheckpointResult<bool> {
+    let data = match std::fs::read(path) {
+        Ok(data) => data,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+        Err(error) => return Err(error.into()),
+    };
+    let header_end = data
+        .iter()
+        .position(|&b| b == b'\n')
+        .ok_or("missing checkpoint header")?;
+    let header = std::str::from_utf8(&data[..header_end])?;
+    let tokens: Vec<&str> = header.split_whitespace().collect();
+    if tokens.len() != 5 || tokens[0] != "CHECKPOINT" {
+        return Err(format!("not a checkpoint file: {}", path.display()).into());
+    }
+    if tokens[1].parse::<u32>()? != VERSION {
+        return Err(format!("unsupported checkpoint version: {}", tokens[1]).into());
+    }
+    let width: usize = tokens[2].parse()?;
+    let height: usize = tokens[3].parse()?;
+    if width != film.width()
+        || height != film.height()
+        || u64::from_str_radix(tokens[4], 16)? != fingerprint
+    {
+        return Err(format!(
+            "checkpoint {} was made with a different scene or camera",
+            path.display()
+        )
+        .into());
+    }
+
+    let raster = &data[header_end + 1..];
+    if raster.len() < pixels.len() * PIXEL_SIZE {
+        return Err("checkpoint data is truncated".into());
+    }
+    let (sums, weights) = film.accumulators_mut();
+    let records = raster.chunks_exact(PIXEL_SIZE);
+    for (((record, sum), weight), stats) in records.zip(sums).zip(weights).zip(pixels) {
+        let value = |n: usize| {
+            let b = &record[4 * n..4 * n + 4];
+            f32::from_le_bytes([b[0], b[1], b[2], b[3]]) as float
+        };
+        *sum = Color::new(value(0), value(1), value(2));
+        *weight = value(3);
+        *stats = PixelStats {
+            count: u32::from_le_bytes([record[16], record[17], record[18], record[19]]) as usize,
+            mean: Color::new(value(5), value(6), value(7)),
+            squared_deviations: Color::new(value(8), value(9), value(10)),
+            converged: record[PIXEL_SIZE - 1] != 0,
+        };
+    }
+    return Ok(true);
+}
@@ -0,0 +1,111 @@
+use std::rc::Rc;
+
+use crate::{
+    hittable::HitRecord,
+    material::Material,
+    microfacet::{fresnel_conductor, TrowbridgeReitz},
+    prelude::*,
+    sampler::SampleContext,
+    spectrum::rgb_to_spectrum,
+    texture::{SolidColor, Texture},
+    thin_film::{Complex, ThinFilm},
+};
+
+// A metal with a GGX microfacet surface and a complex index of refraction
+// eta + i k given per RGB channel.
+#[derive(Debug)]
+pub struct Conductor {
+    eta: Color,
+    k: Color,
+    roughness: Rc<dyn Texture>,
+    thin_film: Option<ThinFilm>,
+}
+
+impl Conductor {
+    pub fn new(eta: Color, k: Color, roughness: float) -> Self {
+        Self::from_texture(eta, k, Rc::new(SolidColor::new(Color::uniform(roughness))))
+    }
+
+    pub fn from_texture(eta: Color, k: Color, roughness: Rc<dyn Texture>) -> Self {
+        Self {
+            eta,
+            k,
+            roughness,
+            thin_film: None,
+        }
+    }
+
+    // Coats the metal with a transparent film, e.g. an anodized oxide layer.
+    #[inline]
+    pub fn with_thin_film(mut self, thin_film: ThinFilm) -> Self {
+        self.thin_film = Some(thin_film);
+        self
+    }
+
+    pub fn gold(roughness: float) -> Self {
+        Self::new(
+            Color::new(0.143, 0.374, 1.442),
+            Color::new(3.983, 2.386, 1.603),
+            roughness,
+        )
+    }
+
+    pub fn copper(roughness: float) -> Self {
+        Self::new(
+            Color::new(0.200, 0.924, 1.102),
+            Color::new(3.912, 2.452, 2.142),
+            roughness,
+        )
+    }
+
+    pub fn aluminum(roughness: float) -> Self {
+        Self::new(
+            Color::new(1.657, 0.880, 0.521),
+            Color::new(9.224, 6.270, 4.837),
+            roughness,
+        )
+    }
+
+    pub fn silver(roughness: float) -> Self {
+        Self::new(
+            Color::new(0.155, 0.117, 0.138),
+            Color::new(4.828, 3.122, 2.147),
+            roughness,
+        )
+    }
+}
+
+impl Material for Conductor {
+    fn scatter(
+        &self,
+        in_ray: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut SampleContext,
+    ) -> Option<(Color, Ray)> {
+        let frame = Frame::from_normal(&hit_record.normal);
+        let wo = frame.to_local(&-in_ray.direction().unit());
+        if wo.z() <= 0.0 {
+            return None;
+        }
+
+        let roughness =
+            self.roughness
+                .scalar_value(hit_record.u, hit_record.v, &hit_record.position);
+        let distribution = TrowbridgeReitz::from_roughness(roughness);
+        let (wi, wm, weight) = distribution.sample_reflection(&wo, sampler)?;
+        let fresnel = match &self.thin_film {
+            Some(thin_film) => {
+                thin_film.reflectance(hit_record, wo.dot(&wm), in_ray.wavelength(), |lambda| {
+                    let eta = rgb_to_spectrum(&self.eta, lambda);
+                    let k = rgb_to_spectrum(&self.k, lambda);
+                    (Complex::real(1.0), Complex::new(eta, k))
+                })
+            }
+            None => fresnel_conductor(wo.dot(&wm), &self.eta, &self.k),
+        };
+        return Some((
+            weight * fresnel,
+            in_ray.spawn(hit_record.position, frame.to_world(&wi)),
+        ));
+    }
+}
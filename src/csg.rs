@@ -0,0 +1,138 @@
+use crate::{
+    aabb::Aabb,
+    hittable::{HitRecord, Hittable},
+    interval::Interval,
+    prelude::*,
+};
+
+// A stretch of a ray inside a solid, with the surfaces where it enters and
+// leaves it.
+#[derive(Clone, Debug)]
+pub struct Span {
+    pub enter: HitRecord,
+    pub exit: HitRecord,
+}
+
+// A closed object that can report every span of a ray inside it, not just the
+// closest hit. Spans cover the whole line, including behind the ray origin,
+// and are sorted and disjoint.
+pub trait Solid: Hittable {
+    fn spans(&self, ray: &Ray) -> Vec<Span>;
+}
+
+// The closest span boundary within `t_interval`, for solids to implement
+// `Hittable::hit` with.
+pub fn closest_boundary(spans: Vec<Span>, t_interval: Interval) -> Option<HitRecord> {
+    spans
+        .into_iter()
+        .flat_map(|span| [span.enter, span.exit])
+        .find(|hit| t_interval.surrounds(hit.t))
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Operation {
+    Union,
+    Intersection,
+    Difference,
+}
+
+impl Operation {
+    fn contains(&self, in_left: bool, in_right: bool) -> bool {
+        match self {
+            Operation::Union => in_left || in_right,
+            Operation::Intersection => in_left && in_right,
+            Operation::Difference => in_left && !in_right,
+        }
+    }
+}
+
+// Combines two solids with a boolean operation. The result is a solid as
+// well, so trees of them can be built.
+pub struct Csg {
+    operation: Operation,
+    left: Box<dyn Solid>,
+    right: Box<dyn Solid>,
+}
+
+impl Csg {
+    pub fn new(operation: Operation, left: Box<dyn Solid>, right: Box<dyn Solid>) -> Self {
+        Self {
+            operation,
+            left,
+            right,
+        }
+    }
+
+    pub fn union(left: Box<dyn Solid>, right: Box<dyn Solid>) -> Self {
+        Self::new(Operation::Union, left, right)
+    }
+
+    pub fn intersection(left: Box<dyn Solid>, right: Box<dyn Solid>) -> Self {
+        Self::new(Operation::Intersection, left, right)
+    }
+
+    // Carves `right` out of `left`.
+    pub fn difference(left: Box<dyn Solid>, right: Box<dyn Solid>) -> Self {
+        Self::new(Operation::Difference, left, right)
+    }
+}
+
+impl Solid for Csg {
+    fn spans(&self, ray: &Ray) -> Vec<Span> {
+        // Walk the boundaries of both operands in order, tracking which ones
+        // the ray is inside of, and keep those where the result changes.
+        let boundaries = |spans: Vec<Span>, is_left: bool| {
+            spans
+                .into_iter()
+                .flat_map(move |span| [(span.enter, is_left, true), (span.exit, is_left, false)])
+        };
+        let mut events: Vec<_> = boundaries(self.left.spans(ray), true)
+            .chain(boundaries(self.right.spans(ray), false))
+            .collect();
+        // Entries go first on ties so that touching solids merge without a
+        // surface between them.
+        events.sort_by(|a, b| a.0.t.total_cmp(&b.0.t).then(b.2.cmp(&a.2)));
+
+        let (mut in_left, mut in_right) = (false, false);
+        let mut enter = None;
+        let mut spans = vec![];
+        for (mut hit, is_left, entering) in events {
+            let was_inside = self.operation.contains(in_left, in_right);
+            if is_left {
+                in_left = entering;
+            } else {
+                in_right = entering;
+            }
+            let inside = self.operation.contains(in_left, in_right);
+            if inside == was_inside {
+                continue;
+            }
+
+            // Carved surfaces face into the subtracted solid.
+            if matches!(self.operation, Operation::Difference) && !is_left {
+                hit.front_face = !hit.front_face;
+            }
+            if inside {
+                enter = Some(hit);
+            } else if let Some(enter) = enter.take() {
+                spans.push(Span { enter, exit: hit });
+            }
+        }
+        return spans;
+    }
+}
+
+impl Hittable for Csg {
+    fn hit(&self, ray: &Ray, t_interval: Interval) -> Option<HitRecord> {
+        closest_boundary(self.spans(ray), t_interval)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let (left, right) = (self.left.bounding_box(), self.right.bounding_box());
+        match self.operation {
+            Operation::Union => left.union(&right),
+            Operation::Intersection => left.intersection(&right),
+            Operation::Difference => left,
+        }
+    }
+}
@@ -0,0 +1,100 @@
+use std::rc::Rc;
+
+use crate::{
+    aabb::Aabb,
+    csg::{closest_boundary, Solid, Span},
+    hittable::{HitRecord, Hittable},
+    interval::Interval,
+    material::Material,
+    prelude::*,
+};
+
+// An axis aligned box. Rotate it with a `Transformed` if needed.
+pub struct Cuboid {
+    bounds: Aabb,
+    material: Rc<dyn Material>,
+}
+
+impl Cuboid {
+    pub fn new(a: Point3, b: Point3, material: Rc<dyn Material>) -> Self {
+        Self {
+            bounds: Aabb::from_points(a, b),
+            material,
+        }
+    }
+
+    // Each face is parameterized over the two other axes in cyclic order, so
+    // that dp/du x dp/dv points out of the box.
+    fn record(&self, ray: &Ray, t: float, axis: usize, sign: float) -> HitRecord {
+        let position = ray.at(t);
+        let (min, size) = (self.bounds.min(), self.bounds.max() - self.bounds.min());
+        let (u_axis, v_axis) = ((axis + 1) % 3, (axis + 2) % 3);
+        let along = |axis: usize| {
+            let mut e = [0.0; 3];
+            e[axis] = size.e[axis];
+            Vec3::from_array(e)
+        };
+
+        let mut outward_normal = Vec3::ZERO;
+        outward_normal.e[axis] = sign;
+        let u = (position.e[u_axis] - min.e[u_axis]) / size.e[u_axis];
+        let v = (position.e[v_axis] - min.e[v_axis]) / size.e[v_axis];
+        let (u, dpdu) = if sign > 0.0 {
+            (u, along(u_axis))
+        } else {
+            (1.0 - u, -along(u_axis))
+        };
+
+        return HitRecord::from_outward_normal(
+            t,
+            position,
+            self.material.clone(),
+            outward_normal,
+            ray,
+        )
+        .with_uv(u, v)
+        .with_tangents(dpdu, along(v_axis));
+    }
+}
+
+impl Solid for Cuboid {
+    fn spans(&self, ray: &Ray) -> Vec<Span> {
+        let origin = ray.origin();
+        let direction = ray.direction();
+        let (mut enter, mut exit) = ((float::NEG_INFINITY, 0), (float::INFINITY, 0));
+        for axis in 0..3 {
+            let slab = self.bounds.axis(axis);
+            let inverse_direction = 1.0 / direction.e[axis];
+            let t0 = (slab.min - origin.e[axis]) * inverse_direction;
+            let t1 = (slab.max - origin.e[axis]) * inverse_direction;
+            let (t0, t1) = if t0 < t1 { (t0, t1) } else { (t1, t0) };
+            if t0 > enter.0 {
+                enter = (t0, axis);
+            }
+            if t1 < exit.0 {
+                exit = (t1, axis);
+            }
+        }
+        if enter.0 >= exit.0 || enter.0.is_infinite() || exit.0.is_infinite() {
+            return vec![];
+        }
+
+        // Rays enter through the faces they move into and leave through the
+        // ones they move out of.
+        let sign = |axis: usize| direction.e[axis].signum();
+        return vec![Span {
+            enter: self.record(ray, enter.0, enter.1, -sign(enter.1)),
+            exit: self.record(ray, exit.0, exit.1, sign(exit.1)),
+        }];
+    }
+}
+
+impl Hittable for Cuboid {
+    fn hit(&self, ray: &Ray, t_interval: Interval) -> Option<HitRecord> {
+        closest_boundary(self.spans(ray), t_interval)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bounds
+    }
+}
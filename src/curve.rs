@@ -0,0 +1,247 @@
+use std::rc::Rc;
+
+use crate::{
+    aabb::Aabb,
+    hittable::{HitRecord, Hittable},
+    interval::Interval,
+    material::Material,
+    prelude::*,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CurveShape {
+    // A flat strip that always faces the ray. Enough for thin hair, where the
+    // `Hair` material accounts for the round fiber itself.
+    Ribbon,
+    // The same strip shaded with the normals of a round tube, for thicker
+    // strands seen up close.
+    Cylinder,
+}
+
+// A cubic Bézier curve swept with a radius that varies linearly from start to
+// end. Rays are intersected by subdividing the curve until the pieces are
+// close enough to straight, as in pbrt. The hit's u runs along the curve and v
+// across it, with 0.5 at the center line, and dp/du follows the curve.
+pub struct Curve {
+    control_points: [Point3; 4],
+    radii: [float; 2],
+    u_range: [float; 2],
+    shape: CurveShape,
+    max_depth: u32,
+    material: Rc<dyn Material>,
+}
+
+impl Curve {
+    pub fn new(
+        control_points: [Point3; 4],
+        start_radius: float,
+        end_radius: float,
+        material: Rc<dyn Material>,
+    ) -> Self {
+        // Subdivide until the pieces bend away from their chords by a small
+        // fraction of the width.
+        let bend = (0..2)
+            .map(|i| {
+                let [a, b, c] = [0, 1, 2].map(|j| control_points[i + j]);
+                (a - 2.0 * b + c).lenght()
+            })
+            .fold(0.0, float::max);
+        let tolerance = 0.05 * 2.0 * start_radius.max(end_radius);
+        let max_depth = if tolerance > 0.0 {
+            (0.5 * (6.0 * float::sqrt(2.0) * bend / (8.0 * tolerance)).log2()).clamp(0.0, 10.0)
+                as u32
+        } else {
+            0
+        };
+        Self {
+            control_points,
+            radii: [start_radius, end_radius],
+            u_range: [0.0, 1.0],
+            shape: CurveShape::Cylinder,
+            max_depth,
+            material,
+        }
+    }
+
+    // A smooth strand through all of `points`, as one Catmull-Rom segment per
+    // pair of neighbors. The radius and u vary over the whole strand.
+    pub fn strand(
+        points: &[Point3],
+        start_radius: float,
+        end_radius: float,
+        material: Rc<dyn Material>,
+    ) -> Vec<Self> {
+        assert!(points.len() >= 2, "strands need at least 2 points");
+        let segments = points.len() - 1;
+        let point = |i: isize| points[i.clamp(0, segments as isize) as usize];
+        let radius = |i: usize| {
+            let f = i as float / segments as float;
+            (1.0 - f) * start_radius + f * end_radius
+        };
+        (0..segments)
+            .map(|i| {
+                let j = i as isize;
+                let (p0, p1, p2, p3) = (point(j - 1), point(j), point(j + 1), point(j + 2));
+                let mut curve = Self::new(
+                    [p1, p1 + (p2 - p0) / 6.0, p2 - (p3 - p1) / 6.0, p2],
+                    radius(i),
+                    radius(i + 1),
+                    material.clone(),
+                );
+                curve.u_range = [i as float, (i + 1) as float].map(|u| u / segments as float);
+                curve
+            })
+            .collect()
+    }
+
+    #[inline]
+    pub fn with_shape(mut self, shape: CurveShape) -> Self {
+        self.shape = shape;
+        self
+    }
+
+    #[inline]
+    fn radius(&self, s: float) -> float {
+        (1.0 - s) * self.radii[0] + s * self.radii[1]
+    }
+
+    // Tests the piece of the curve between `s0` and `s1`, given its control
+    // points in ray space: the ray runs along +z from the origin, where z is
+    // the distance along it.
+    fn hit_piece(
+        &self,
+        ray: &Ray,
+        frame: &Frame,
+        points: &[Vec3; 4],
+        [s0, s1]: [float; 2],
+        depth: u32,
+        z_interval: &mut Interval,
+    ) -> Option<HitRecord> {
+        let radius = self.radius(s0).max(self.radius(s1));
+        let bounds =
+            Aabb::from_points(points[0], points[1]).union(&Aabb::from_points(points[2], points[3]));
+        if !bounds.axis(0).expand(2.0 * radius).contains(0.0)
+            || !bounds.axis(1).expand(2.0 * radius).contains(0.0)
+            || bounds.axis(2).intersection(z_interval).size() < 0.0
+        {
+            return None;
+        }
+
+        if depth > 0 {
+            let [a, b, c, d] = *points;
+            let middle = (a + 3.0 * b + 3.0 * c + d) / 8.0;
+            let halves = [
+                [a, (a + b) / 2.0, (a + 2.0 * b + c) / 4.0, middle],
+                [middle, (b + 2.0 * c + d) / 4.0, (c + d) / 2.0, d],
+            ];
+            let s_middle = (s0 + s1) / 2.0;
+            let mut closest = None;
+            for (half, range) in halves.iter().zip([[s0, s_middle], [s_middle, s1]]) {
+                if let Some(hit) = self.hit_piece(ray, frame, half, range, depth - 1, z_interval) {
+                    closest = Some(hit);
+                }
+            }
+            return closest;
+        }
+
+        // Only accept hits between the lines through the ends perpendicular
+        // to the curve, so neighboring pieces don't overlap.
+        let [a, b, c, d] = *points;
+        if (b.y() - a.y()) * -a.y() + a.x() * (a.x() - b.x()) < 0.0
+            || (c.y() - d.y()) * -d.y() + d.x() * (d.x() - c.x()) < 0.0
+        {
+            return None;
+        }
+
+        // Closest point on the chord to the ray, then on the curve near it.
+        let chord = d - a;
+        let w = (-(a.x() * chord.x() + a.y() * chord.y())
+            / (chord.x() * chord.x() + chord.y() * chord.y()))
+        .clamp(0.0, 1.0);
+        let (center, _) = evaluate_bezier(points, w);
+        let s = s0 + w * (s1 - s0);
+        let radius = self.radius(s);
+        let distance_squared = center.x() * center.x() + center.y() * center.y();
+        if distance_squared > radius * radius || !z_interval.surrounds(center.z()) {
+            return None;
+        }
+        *z_interval = z_interval.with_max(center.z());
+
+        let t = center.z() / ray.direction().lenght();
+        return Some(self.record(ray, frame, t, s, center));
+    }
+
+    fn record(&self, ray: &Ray, frame: &Frame, t: float, s: float, center: Vec3) -> HitRecord {
+        let (_, derivative) = evaluate_bezier(&self.control_points, s);
+        let [u0, u1] = self.u_range;
+        let dpdu = derivative / (u1 - u0);
+        let tangent = dpdu.unit();
+
+        // The strip faces the ray, with v increasing towards its side.
+        let towards_ray = -frame.z - tangent.dot(&-frame.z) * tangent;
+        let facing = if towards_ray.near_zero() {
+            Frame::from_normal(&tangent).x
+        } else {
+            towards_ray.unit()
+        };
+        let side = facing.cross(&tangent);
+        let radius = self.radius(s);
+        let offset = frame.to_world(&Vec3::new(-center.x(), -center.y(), 0.0));
+        let h = (offset.dot(&side) / radius).clamp(-1.0, 1.0);
+
+        let mut hit_record =
+            HitRecord::from_outward_normal(t, ray.at(t), self.material.clone(), facing, ray)
+                .with_uv(u0 + s * (u1 - u0), 0.5 + 0.5 * h)
+                .with_tangents(dpdu, 2.0 * radius * side);
+        if self.shape == CurveShape::Cylinder {
+            hit_record.set_outward_shading_normal(h * side + (1.0 - h * h).sqrt() * facing);
+        }
+        return hit_record;
+    }
+}
+
+impl Hittable for Curve {
+    fn hit(&self, ray: &Ray, t_interval: Interval) -> Option<HitRecord> {
+        self.bounding_box().hit(ray, t_interval)?;
+        let speed = ray.direction().lenght();
+        let frame = Frame::from_normal(&(ray.direction() / speed));
+        let points = self
+            .control_points
+            .map(|p| frame.to_local(&(p - ray.origin())));
+        let mut z_interval = Interval::new(t_interval.min * speed, t_interval.max * speed);
+        return self.hit_piece(
+            ray,
+            &frame,
+            &points,
+            [0.0, 1.0],
+            self.max_depth,
+            &mut z_interval,
+        );
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let radius = self.radii[0].max(self.radii[1]);
+        let [a, b, c, d] = self.control_points;
+        let bounds = Aabb::from_points(a, b).union(&Aabb::from_points(c, d));
+        Aabb::new(
+            bounds.axis(0).expand(2.0 * radius),
+            bounds.axis(1).expand(2.0 * radius),
+            bounds.axis(2).expand(2.0 * radius),
+        )
+    }
+}
+
+// The point at `s` on a cubic Bézier curve and the derivative there, by de
+// Casteljau's algorithm.
+fn evaluate_bezier(points: &[Vec3; 4], s: float) -> (Vec3, Vec3) {
+    let lerp = |a: Vec3, b: Vec3| (1.0 - s) * a + s * b;
+    let [a, b, c, d] = *points;
+    let (ab, bc, cd) = (lerp(a, b), lerp(b, c), lerp(c, d));
+    let (abc, bcd) = (lerp(ab, bc), lerp(bc, cd));
+    let derivative = if (bcd - abc).near_zero() {
+        d - a
+    } else {
+        3.0 * (bcd - abc)
+    };
+    return (lerp(abc, bcd), derivative);
+}
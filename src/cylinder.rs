@@ -0,0 +1,163 @@
+use std::rc::Rc;
+
+use crate::{
+    aabb::Aabb,
+    csg::{closest_boundary, Solid, Span},
+    hittable::{HitRecord, Hittable},
+    interval::Interval,
+    material::Material,
+    prelude::*,
+};
+
+#[derive(Clone, Copy, PartialEq)]
+enum Surface {
+    Side,
+    Bottom,
+    Top,
+}
+
+// A capped cylinder between the centers of its two caps.
+pub struct Cylinder {
+    base: Point3,
+    frame: Frame,
+    height: float,
+    radius: float,
+    material: Rc<dyn Material>,
+}
+
+impl Cylinder {
+    pub fn new(base: Point3, top: Point3, radius: float, material: Rc<dyn Material>) -> Self {
+        let axis = top - base;
+        Self {
+            base,
+            frame: Frame::from_normal(&axis.unit()),
+            height: axis.lenght(),
+            radius,
+            material,
+        }
+    }
+
+    // The side is parameterized by the angle around the axis and the height,
+    // and the caps by their local x and y, so that dp/du x dp/dv points out.
+    fn record(&self, ray: &Ray, t: float, surface: Surface) -> HitRecord {
+        let position = ray.at(t);
+        let local = self.frame.to_local(&(position - self.base));
+        let (x, y, r) = (local.x(), local.y(), self.radius);
+
+        let (normal, u, v, dpdu, dpdv) = match surface {
+            Surface::Side => {
+                let phi = y.atan2(x);
+                let phi = if phi < 0.0 { phi + 2.0 * PI } else { phi };
+                (
+                    Vec3::new(x / r, y / r, 0.0),
+                    phi / (2.0 * PI),
+                    local.z() / self.height,
+                    2.0 * PI * Vec3::new(-y, x, 0.0),
+                    Vec3::new(0.0, 0.0, self.height),
+                )
+            }
+            Surface::Bottom | Surface::Top => {
+                let sign = if surface == Surface::Top { 1.0 } else { -1.0 };
+                (
+                    Vec3::new(0.0, 0.0, sign),
+                    0.5 + sign * x / (2.0 * r),
+                    0.5 + y / (2.0 * r),
+                    Vec3::new(sign * 2.0 * r, 0.0, 0.0),
+                    Vec3::new(0.0, 2.0 * r, 0.0),
+                )
+            }
+        };
+
+        return HitRecord::from_outward_normal(
+            t,
+            position,
+            self.material.clone(),
+            self.frame.to_world(&normal).unit(),
+            ray,
+        )
+        .with_uv(u, v)
+        .with_tangents(self.frame.to_world(&dpdu), self.frame.to_world(&dpdv));
+    }
+}
+
+impl Solid for Cylinder {
+    fn spans(&self, ray: &Ray) -> Vec<Span> {
+        let origin = self.frame.to_local(&(ray.origin() - self.base));
+        let direction = self.frame.to_local(&ray.direction());
+
+        // The cylinder is the overlap of an infinite tube and the slab between
+        // the caps.
+        let a = direction.x() * direction.x() + direction.y() * direction.y();
+        let half_b = origin.x() * direction.x() + origin.y() * direction.y();
+        let c = origin.x() * origin.x() + origin.y() * origin.y() - self.radius * self.radius;
+        let tube = if a > 1e-12 {
+            let delta = half_b * half_b - a * c;
+            if delta <= 0.0 {
+                return vec![];
+            }
+            let delta_sqrt = delta.sqrt();
+            Interval::new((-half_b - delta_sqrt) / a, (-half_b + delta_sqrt) / a)
+        } else if c < 0.0 {
+            Interval::UNIVERSE
+        } else {
+            return vec![];
+        };
+
+        let slab = if direction.z().abs() > 1e-12 {
+            let t0 = -origin.z() / direction.z();
+            let t1 = (self.height - origin.z()) / direction.z();
+            Interval::new(t0.min(t1), t0.max(t1))
+        } else if (0.0..=self.height).contains(&origin.z()) {
+            Interval::UNIVERSE
+        } else {
+            return vec![];
+        };
+
+        let span = tube.intersection(&slab);
+        if span.min >= span.max || span.min.is_infinite() || span.max.is_infinite() {
+            return vec![];
+        }
+
+        // Caps are crossed where the slab is the tighter bound, bottom first
+        // when moving up the axis.
+        let upwards = direction.z() > 0.0;
+        let enter = if slab.min > tube.min {
+            if upwards {
+                Surface::Bottom
+            } else {
+                Surface::Top
+            }
+        } else {
+            Surface::Side
+        };
+        let exit = if slab.max < tube.max {
+            if upwards {
+                Surface::Top
+            } else {
+                Surface::Bottom
+            }
+        } else {
+            Surface::Side
+        };
+        return vec![Span {
+            enter: self.record(ray, span.min, enter),
+            exit: self.record(ray, span.max, exit),
+        }];
+    }
+}
+
+impl Hittable for Cylinder {
+    fn hit(&self, ray: &Ray, t_interval: Interval) -> Option<HitRecord> {
+        closest_boundary(self.spans(ray), t_interval)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        // Each cap is a disk whose extent along an axis shrinks as the
+        // cylinder axis lines up with it.
+        let axis = self.frame.z;
+        let extent = axis.map(|a| self.radius * (1.0 - a * a).max(0.0).sqrt());
+        let top = self.base + self.height * axis;
+        Aabb::from_points(self.base - extent, self.base + extent)
+            .union(&Aabb::from_points(top - extent, top + extent))
+    }
+}
@@ -0,0 +1,346 @@
+use std::{
+    collections::VecDeque,
+    error::Error,
+    io::{BufRead, BufReader, BufWriter, Write},
+    net::{TcpListener, TcpStream},
+    sync::{mpsc, Arc, Condvar, Mutex},
+    thread,
+    time::Duration,
+};
+
+use crate::{camera::CameraBuilder, hittable_list::HittableList, image::Image, prelude::*};
+
+pub type ProtocolResult<T> = Result<T, Box<dyn Error>>;
+
+// How often workers tell the coordinator they are still rendering. Timeouts
+// must be a good deal longer.
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+// Builds the world and camera of a job. The coordinator and every worker call
+// it, so it must build the same scene for the same job, e.g. from its seed.
+pub type SceneBuilder = fn(&Job) -> (HittableList, CameraBuilder);
+
+// A frame rendered in tiles by workers, which all build the same scene from
+// the job's seed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Job {
+    pub seed: u64,
+    pub image_width: usize,
+    pub samples_per_pixel: usize,
+    // Columns and rows of tiles.
+    pub grid: [usize; 2],
+}
+
+impl Job {
+    // The scene `builder` makes for the job, with the job's image width and
+    // sample count.
+    pub fn scene(&self, builder: SceneBuilder) -> (HittableList, CameraBuilder) {
+        let (world, camera) = builder(self);
+        let camera = camera
+            .with_image_width(self.image_width)
+            .with_samples_per_pixel(self.samples_per_pixel);
+        return (world, camera);
+    }
+
+    pub fn tiles(&self) -> impl Iterator<Item = [usize; 2]> {
+        let [columns, rows] = self.grid;
+        (0..rows).flat_map(move |row| (0..columns).map(move |column| [column, row]))
+    }
+}
+
+// The messages between a coordinator and its workers. Workers connect to the
+// coordinator, which sends them the job and then one tile at a time, each
+// answered with its pixels, until there are no tiles left. Workers send a
+// heartbeat every `HEARTBEAT_INTERVAL` in between, so that the coordinator
+// can tell a long tile from a dead worker:
+//
+//   coordinator: JOB <seed> <width> <samples per pixel> <columns> <rows>
+//   coordinator: TILE <column> <row>
+//   worker:      ALIVE
+//   worker:      PIXELS <x> <y> <width> <height>
+//                followed by linear RGB pixels, row by row, as little endian
+//                32 bit floats
+//   coordinator: QUIT
+#[derive(Debug)]
+pub enum Message {
+    Job(Job),
+    Tile([usize; 2]),
+    // A rendered tile with its top left corner in the frame.
+    Pixels([usize; 2], Image),
+    Alive,
+    Quit,
+}
+
+pub fn send(output: &mut impl Write, message: &Message) -> ProtocolResult<()> {
+    match message {
+        Message::Job(job) => writeln!(
+            output,
+            "JOB {} {} {} {} {}",
+            job.seed, job.image_width, job.samples_per_pixel, job.grid[0], job.grid[1]
+        )?,
+        Message::Tile([column, row]) => writeln!(output, "TILE {} {}", column, row)?,
+        Message::Pixels([x, y], image) => {
+            writeln!(
+                output,
+                "PIXELS {} {} {} {}",
+                x,
+                y,
+                image.width(),
+                image.height()
+            )?;
+            let mut data = Vec::with_capacity(image.width() * image.height() * 12);
+            for j in 0..image.height() {
+                for i in 0..image.width() {
+                    for value in image.pixel(i, j).e {
+                        data.extend_from_slice(&value.to_le_bytes());
+                    }
+                }
+            }
+            output.write_all(&data)?;
+        }
+        Message::Alive => writeln!(output, "ALIVE")?,
+        Message::Quit => writeln!(output, "QUIT")?,
+    }
+    output.flush()?;
+    Ok(())
+}
+
+// Fails if the connection closes before a whole message arrives. Pixels are
+// only read up to `max_pixels`, so that a corrupt header can't make it
+// allocate any amount of memory.
+pub fn receive(input: &mut impl BufRead, max_pixels: usize) -> ProtocolResult<Message> {
+    let mut line = String::new();
+    if input.read_line(&mut line)? == 0 {
+        return Err("connection closed".into());
+    }
+    let tokens: Vec<&str> = line.split_whitespace().collect();
+    let number = |n: usize| -> ProtocolResult<usize> {
+        let token = tokens.get(n).ok_or("incomplete message")?;
+        Ok(token.parse()?)
+    };
+    let message = match tokens.first().copied() {
+        Some("JOB") => Message::Job(Job {
+            seed: tokens.get(1).ok_or("incomplete message")?.parse()?,
+            image_width: number(2)?,
+            samples_per_pixel: number(3)?,
+            grid: [number(4)?, number(5)?],
+        }),
+        Some("TILE") => Message::Tile([number(1)?, number(2)?]),
+        Some("PIXELS") => {
+            let (width, height) = (number(3)?, number(4)?);
+            let size = width
+                .checked_mul(height)
+                .filter(|&count| count <= max_pixels)
+                .and_then(|count| count.checked_mul(12))
+                .ok_or_else(|| format!("unexpected tile size: {}x{}", width, height))?;
+            let mut data = vec![0; size];
+            input.read_exact(&mut data)?;
+            let pixels = data
+                .chunks_exact(12)
+                .map(|b| {
+                    let value = |n: usize| {
+                        f32::from_le_bytes([b[4 * n], b[4 * n + 1], b[4 * n + 2], b[4 * n + 3]])
+                            as float
+                    };
+                    Color::new(value(0), value(1), value(2))
+                })
+                .collect();
+            Message::Pixels([number(1)?, number(2)?], Image::new(width, height, pixels))
+        }
+        Some("ALIVE") => Message::Alive,
+        Some("QUIT") => Message::Quit,
+        _ => return Err(format!("unknown message: {}", line.trim()).into()),
+    };
+    return Ok(message);
+}
+
+// The tiles not handed out yet, shared by the threads serving workers.
+struct Queue {
+    state: Mutex<State>,
+    changed: Condvar,
+}
+
+struct State {
+    pending: VecDeque<[usize; 2]>,
+    remaining: usize,
+    workers: usize,
+}
+
+// Hands the job's tiles out to the workers connecting to `listener` and
+// stitches their pixels into the frame. Tiles of workers that disconnect, or
+// send nothing for `timeout`, go back in the queue for the others. Fails if
+// the timeout isn't longer than `HEARTBEAT_INTERVAL`, as healthy workers would
+// time out too.
+pub fn coordinate(
+    listener: TcpListener,
+    job: Job,
+    scene: SceneBuilder,
+    timeout: Duration,
+) -> ProtocolResult<Image> {
+    if timeout <= HEARTBEAT_INTERVAL {
+        return Err(format!(
+            "the timeout must be longer than the {} second heartbeat interval",
+            HEARTBEAT_INTERVAL.as_secs()
+        )
+        .into());
+    }
+    let camera = job.scene(scene).1.build();
+    let (width, height) = (camera.image_width(), camera.image_height());
+
+    let pending: VecDeque<_> = job.tiles().collect();
+    let count = pending.len();
+    let queue = Arc::new(Queue {
+        state: Mutex::new(State {
+            pending,
+            remaining: count,
+            workers: 0,
+        }),
+        changed: Condvar::new(),
+    });
+
+    let (results, finished) = mpsc::channel();
+    {
+        let queue = queue.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let (queue, results) = (queue.clone(), results.clone());
+                queue.state.lock().unwrap().workers += 1;
+                thread::spawn(move || {
+                    serve_worker(stream, job, scene, timeout, &queue, results);
+                    queue.state.lock().unwrap().workers -= 1;
+                    queue.changed.notify_all();
+                });
+            }
+        });
+    }
+
+    let mut frame = Image::new(width, height, vec![Color::ZERO; width * height]);
+    for done in 1..=count {
+        let ([x, y], tile) = finished.recv()?;
+        frame.paste(x, y, &tile);
+        eprintln!("Tile progress: {}/{}", done, count);
+    }
+
+    // Give the idle workers a moment to be told to quit.
+    let state = queue.state.lock().unwrap();
+    let _ = queue
+        .changed
+        .wait_timeout_while(state, Duration::from_secs(5), |state| state.workers > 0);
+    return Ok(frame);
+}
+
+// Hands tiles to one worker until none are left, or it fails, in which case
+// its current tile is put back.
+fn serve_worker(
+    stream: TcpStream,
+    job: Job,
+    scene: SceneBuilder,
+    timeout: Duration,
+    queue: &Queue,
+    results: mpsc::Sender<([usize; 2], Image)>,
+) {
+    let peer = stream
+        .peer_addr()
+        .map_or_else(|_| "unknown".to_string(), |a| a.to_string());
+    eprintln!("Worker {} connected", peer);
+    let _ = stream.set_read_timeout(Some(timeout));
+    let (mut reader, mut writer) = match stream.try_clone() {
+        Ok(clone) => (BufReader::new(clone), BufWriter::new(stream)),
+        Err(_) => return,
+    };
+    if send(&mut writer, &Message::Job(job)).is_err() {
+        return;
+    }
+
+    let (_, camera) = job.scene(scene);
+    loop {
+        let tile = {
+            let mut state = queue.state.lock().unwrap();
+            loop {
+                if state.remaining == 0 {
+                    let _ = send(&mut writer, &Message::Quit);
+                    return;
+                }
+                if let Some(tile) = state.pending.pop_front() {
+                    break tile;
+                }
+                state = queue.changed.wait(state).unwrap();
+            }
+        };
+
+        let window = camera
+            .clone()
+            .with_tile(tile, job.grid)
+            .build()
+            .crop_window();
+        let size = [window[1][0] - window[0][0], window[1][1] - window[0][1]];
+        let result = send(&mut writer, &Message::Tile(tile)).and_then(|_| loop {
+            match receive(&mut reader, size[0] * size[1]) {
+                Ok(Message::Alive) => continue,
+                result => break result,
+            }
+        });
+        match result {
+            Ok(Message::Pixels(origin, image))
+                if origin == window[0] && [image.width(), image.height()] == size =>
+            {
+                queue.state.lock().unwrap().remaining -= 1;
+                queue.changed.notify_all();
+                let _ = results.send((origin, image));
+            }
+            result => {
+                match result {
+                    Ok(message) => eprintln!("Worker {} sent unexpected {:?}", peer, message),
+                    Err(error) => eprintln!("Worker {} failed: {}", peer, error),
+                }
+                eprintln!("Requeuing tile {:?}", tile);
+                queue.state.lock().unwrap().pending.push_back(tile);
+                queue.changed.notify_all();
+                return;
+            }
+        }
+    }
+}
+
+// Renders the tiles a coordinator sends over `stream` until it says to quit,
+// with the same scene builder as the coordinator. A separate thread sends
+// heartbeats all the while, sharing the writer.
+pub fn work(stream: TcpStream, scene: SceneBuilder) -> ProtocolResult<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let writer = Arc::new(Mutex::new(BufWriter::new(stream)));
+
+    // The heartbeats stop once the sender is dropped on returning.
+    let (_stop, stopped) = mpsc::channel::<()>();
+    {
+        let writer = writer.clone();
+        thread::spawn(move || {
+            while let Err(mpsc::RecvTimeoutError::Timeout) =
+                stopped.recv_timeout(HEARTBEAT_INTERVAL)
+            {
+                if send(&mut *writer.lock().unwrap(), &Message::Alive).is_err() {
+                    return;
+                }
+            }
+        });
+    }
+
+    // Workers are never sent pixels.
+    let job = match receive(&mut reader, 0)? {
+        Message::Job(job) => job,
+        message => return Err(format!("expected a job, got {:?}", message).into()),
+    };
+    let (world, camera) = job.scene(scene);
+    loop {
+        match receive(&mut reader, 0)? {
+            Message::Tile(tile) => {
+                let camera = camera.clone().with_tile(tile, job.grid).build();
+                let image = camera.render_image(&world)?;
+                let pixels = Message::Pixels(camera.crop_window()[0], image);
+                send(&mut *writer.lock().unwrap(), &pixels)?;
+            }
+            Message::Quit => return Ok(()),
+            message => return Err(format!("unexpected {:?}", message).into()),
+        }
+    }
+}
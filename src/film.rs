@@ -0,0 +1,108 @@
+use crate::{filter::Filter, image::Image, prelude::*};
+
+// Accumulates samples into pixels, splatting each one into every pixel whose
+// center lies within the filter radius, weighted by the filter. Pixels are the
+// weighted average of the samples around them. A film can cover just a window
+// of a larger image, starting at `origin`; its positions and pixels are still
+// in the coordinates of the whole image.
+#[derive(Debug)]
+pub struct Film {
+    width: usize,
+    height: usize,
+    origin: [usize; 2],
+    filter: Filter,
+    sums: Vec<Color>,
+    weights: Vec<float>,
+}
+
+impl Film {
+    pub fn new(width: usize, height: usize, filter: Filter) -> Self {
+        Self {
+            width,
+            height,
+            origin: [0, 0],
+            filter,
+            sums: vec![Color::ZERO; width * height],
+            weights: vec![0.0; width * height],
+        }
+    }
+
+    #[inline]
+    pub fn with_origin(mut self, origin: [usize; 2]) -> Self {
+        self.origin = origin;
+        self
+    }
+
+    #[inline]
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    #[inline]
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    // The weighted sums of samples and total weights of each pixel, row by
+    // row, for saving and restoring renders.
+    #[inline]
+    pub fn accumulators(&self) -> (&[Color], &[float]) {
+        (&self.sums, &self.weights)
+    }
+
+    #[inline]
+    pub fn accumulators_mut(&mut self) -> (&mut [Color], &mut [float]) {
+        (&mut self.sums, &mut self.weights)
+    }
+
+    #[inline]
+    pub fn origin(&self) -> [usize; 2] {
+        self.origin
+    }
+
+    // Adds a sample taken at `position`, in pixels from the top left corner of
+    // the image, so pixel (x, y) is centered at (x + 0.5, y + 0.5).
+    pub fn add_sample(&mut self, position: [float; 2], color: Color) {
+        let radius = self.filter.radius();
+        let range = |p: float, start: usize, size: usize| {
+            let first = (p - 0.5 - radius).ceil().max(start as float) as usize;
+            let last = ((p - 0.5 + radius).floor() as isize).min((start + size) as isize - 1);
+            first..(last + 1).max(0) as usize
+        };
+        let [x0, y0] = self.origin;
+        for y in range(position[1], y0, self.height) {
+            for x in range(position[0], x0, self.width) {
+                let dx = x as float + 0.5 - position[0];
+                let dy = y as float + 0.5 - position[1];
+                let weight = self.filter.evaluate(dx, dy);
+                if weight != 0.0 {
+                    let index = (y - y0) * self.width + (x - x0);
+                    self.sums[index] += weight * color;
+                    self.weights[index] += weight;
+                }
+            }
+        }
+    }
+
+    // Filters with negative lobes can give slightly negative pixels next to
+    // bright edges, which are clamped to black.
+    pub fn pixel(&self, x: usize, y: usize) -> Color {
+        let index = (y - self.origin[1]) * self.width + (x - self.origin[0]);
+        if self.weights[index] == 0.0 {
+            return Color::ZERO;
+        }
+        return (self.sums[index] / self.weights[index]).map(|c| c.max(0.0));
+    }
+
+    pub fn to_image(&self) -> Image {
+        let pixels = (0..self.width * self.height)
+            .map(|i| {
+                self.pixel(
+                    self.origin[0] + i % self.width,
+                    self.origin[1] + i / self.width,
+                )
+            })
+            .collect();
+        return Image::new(self.width, self.height, pixels);
+    }
+}
@@ -0,0 +1,133 @@
+use crate::prelude::*;
+
+// Reconstruction filters, weighing each sample's contribution to the pixels
+// around it by the offset from their centers, in pixels. All of them are
+// separable, the product of one 1D filter along each axis. Wider filters blur
+// more but alias less; the ones with negative lobes keep edges sharper at the
+// cost of slight ringing.
+#[derive(Clone, Copy, Debug)]
+pub enum Filter {
+    Box { radius: float },
+    Tent { radius: float },
+    // Cut off at the radius and shifted down to reach zero there.
+    Gaussian { radius: float, sigma: float },
+    // Mitchell and Netravali's cubic, with B = C = 1/3 recommended.
+    Mitchell { radius: float, b: float, c: float },
+    // A sinc windowed by a wider sinc that reaches zero at the radius.
+    Lanczos { radius: float },
+}
+
+impl Default for Filter {
+    // Each sample only counts towards its own pixel.
+    fn default() -> Self {
+        Filter::Box { radius: 0.5 }
+    }
+}
+
+impl Filter {
+    // Filters with their usual parameters for a given radius.
+    pub fn gaussian(radius: float) -> Self {
+        Filter::Gaussian {
+            radius,
+            sigma: radius / 3.0,
+        }
+    }
+
+    pub fn mitchell(radius: float) -> Self {
+        Filter::Mitchell {
+            radius,
+            b: 1.0 / 3.0,
+            c: 1.0 / 3.0,
+        }
+    }
+
+    #[inline]
+    pub fn radius(&self) -> float {
+        match *self {
+            Filter::Box { radius }
+            | Filter::Tent { radius }
+            | Filter::Gaussian { radius, .. }
+            | Filter::Mitchell { radius, .. }
+            | Filter::Lanczos { radius } => radius,
+        }
+    }
+
+    #[inline]
+    pub fn evaluate(&self, dx: float, dy: float) -> float {
+        self.evaluate_1d(dx) * self.evaluate_1d(dy)
+    }
+
+    fn evaluate_1d(&self, x: float) -> float {
+        let x = x.abs();
+        let radius = self.radius();
+        if x > radius {
+            return 0.0;
+        }
+        match *self {
+            Filter::Box { .. } => 1.0,
+            Filter::Tent { radius } => radius - x,
+            Filter::Gaussian { radius, sigma } => {
+                let gaussian = |x: float| (-x * x / (2.0 * sigma * sigma)).exp();
+                (gaussian(x) - gaussian(radius)).max(0.0)
+            }
+            Filter::Mitchell { radius, b, c } => {
+                let x = 2.0 * x / radius;
+                let (x2, x3) = (x * x, x * x * x);
+                let value = if x > 1.0 {
+                    (-b - 6.0 * c) * x3
+                        + (6.0 * b + 30.0 * c) * x2
+                        + (-12.0 * b - 48.0 * c) * x
+                        + (8.0 * b + 24.0 * c)
+                } else {
+                    (12.0 - 9.0 * b - 6.0 * c) * x3
+                        + (-18.0 + 12.0 * b + 6.0 * c) * x2
+                        + (6.0 - 2.0 * b)
+                };
+                value / 6.0
+            }
+            Filter::Lanczos { radius } => sinc(x) * sinc(x / radius),
+        }
+    }
+}
+
+#[inline]
+fn sinc(x: float) -> float {
+    if x < 1e-5 {
+        return 1.0;
+    }
+    (PI * x).sin() / (PI * x)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn weights_vanish_at_the_radius_and_are_symmetric() {
+        let filters = [
+            Filter::Box { radius: 1.0 },
+            Filter::Tent { radius: 1.5 },
+            Filter::gaussian(1.5),
+            Filter::mitchell(2.0),
+            Filter::Lanczos { radius: 3.0 },
+        ];
+        for filter in filters {
+            let radius = filter.radius();
+            // The box includes its edge, the others fall off to it.
+            if !matches!(filter, Filter::Box { .. }) {
+                assert!(filter.evaluate_1d(radius).abs() < 1e-6, "{:?}", filter);
+            }
+            assert_eq!(filter.evaluate_1d(radius * 1.001), 0.0, "{:?}", filter);
+            assert!(filter.evaluate_1d(0.0) > 0.0, "{:?}", filter);
+            for i in 1..=20 {
+                let x = radius * i as float / 20.0;
+                assert_eq!(
+                    filter.evaluate_1d(x),
+                    filter.evaluate_1d(-x),
+                    "{:?}",
+                    filter
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use crate::prelude::*;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Frame {
+    pub x: Vec3,
+    pub y: Vec3,
+    pub z: Vec3,
+}
+
+impl Frame {
+    #[inline]
+    pub const fn new(x: Vec3, y: Vec3, z: Vec3) -> Self {
+        Self { x, y, z }
+    }
+
+    // Branchless orthonormal basis from Duff et al., "Building an Orthonormal
+    // Basis, Revisited". `normal` must be unit length.
+    #[inline]
+    pub fn from_normal(normal: &Vec3) -> Self {
+        let sign = 1.0_f32.copysign(normal.z());
+        let a = -1.0 / (sign + normal.z());
+        let b = normal.x() * normal.y() * a;
+        let x = Vec3::new(
+            1.0 + sign * normal.x() * normal.x() * a,
+            sign * b,
+            -sign * normal.x(),
+        );
+        let y = Vec3::new(b, sign + normal.y() * normal.y() * a, -normal.y());
+        Self::new(x, y, *normal)
+    }
+
+    // Builds a frame around `normal` whose x axis follows `tangent` as closely
+    // as possible, e.g. a surface's dp/du.
+    #[inline]
+    pub fn from_normal_tangent(normal: &Vec3, tangent: &Vec3) -> Self {
+        let x = tangent - normal.dot(tangent) * normal;
+        if x.near_zero() {
+            return Self::from_normal(normal);
+        }
+        let x = x.unit();
+        Self::new(x, normal.cross(&x), *normal)
+    }
+
+    #[inline]
+    pub fn to_local(&self, v: &Vec3) -> Vec3 {
+        Vec3::new(v.dot(&self.x), v.dot(&self.y), v.dot(&self.z))
+    }
+
+    #[inline]
+    pub fn to_world(&self, v: &Vec3) -> Vec3 {
+        v.x() * self.x + v.y() * self.y + v.z() * self.z
+    }
+
+    #[inline]
+    pub fn to_mat3(&self) -> Mat3 {
+        Mat3::from_cols(self.x, self.y, self.z)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_from_normal_is_orthonormal() {
+        let normals = [
+            Vec3::Z,
+            Vec3::NEG_Z,
+            Vec3::X,
+            Vec3::new(0.3, -0.5, 0.8).unit(),
+            Vec3::new(-0.2, 0.1, -0.97).unit(),
+            Vec3::new(1e-4, 0.0, -1.0).unit(),
+        ];
+        for normal in normals {
+            let frame = Frame::from_normal(&normal);
+            for axis in [frame.x, frame.y, frame.z] {
+                assert!((axis.lenght() - 1.0).abs() < 1e-5, "{:?}", frame);
+            }
+            for (a, b) in [(frame.x, frame.y), (frame.y, frame.z), (frame.z, frame.x)] {
+                assert!(a.dot(&b).abs() < 1e-5, "{:?}", frame);
+            }
+            assert!((frame.x.cross(&frame.y) - frame.z).lenght() < 1e-5);
+            assert!((frame.z - normal).lenght() < 1e-6);
+        }
+    }
+}
@@ -0,0 +1,342 @@
+use crate::{
+    hittable::HitRecord, material::Material, microfacet::fresnel_dielectric, prelude::*,
+    sampler::SampleContext,
+};
+
+// Scattering paths through the fiber modeled explicitly: reflection (R),
+// transmission (TT) and one internal reflection (TRT). Longer paths share one
+// more lobe.
+const LOBES: usize = 3;
+
+#[derive(Clone, Copy, Debug)]
+enum Pigment {
+    Absorption(Color),
+    Reflectance(Color),
+}
+
+// Hair fiber scattering from Chiang et al., "A Practical and Controllable Hair
+// and Fur Model for Production Path Tracing", 2016, which builds on the lobes
+// of Marschner et al. and d'Eon et al. The fiber is a rough dielectric
+// cylinder with cuticle scales tilting its surface. Meant for `Curve`s, whose
+// dp/du follows the fiber and whose v tells where across it the ray hit.
+#[derive(Debug)]
+pub struct Hair {
+    pigment: Pigment,
+    longitudinal_roughness: float,
+    azimuthal_roughness: float,
+    scale_angle: float,
+    index_of_refraction: float,
+}
+
+impl Hair {
+    // Absorption coefficient inside the fiber, relative to its diameter.
+    pub fn new(absorption: Color) -> Self {
+        Self {
+            pigment: Pigment::Absorption(absorption),
+            longitudinal_roughness: 0.3,
+            azimuthal_roughness: 0.3,
+            scale_angle: 2.0,
+            index_of_refraction: 1.55,
+        }
+    }
+
+    // Natural hair colors from melanin concentrations: eumelanin ranges from
+    // blond around 0.3 through brown at 1.3 to black at 8, and pheomelanin
+    // adds red.
+    pub fn from_melanin(eumelanin: float, pheomelanin: float) -> Self {
+        let eumelanin_absorption = Color::new(0.419, 0.697, 1.37);
+        let pheomelanin_absorption = Color::new(0.187, 0.4, 1.05);
+        Self::new(eumelanin * eumelanin_absorption + pheomelanin * pheomelanin_absorption)
+    }
+
+    // Picks the absorption that makes multiply scattered light in a mass of
+    // hair come out as `color`.
+    pub fn from_color(color: Color) -> Self {
+        Self {
+            pigment: Pigment::Reflectance(color),
+            ..Self::new(Color::ZERO)
+        }
+    }
+
+    // Width of the highlights along the fiber, in (0, 1].
+    #[inline]
+    pub fn with_longitudinal_roughness(mut self, roughness: float) -> Self {
+        self.longitudinal_roughness = roughness.clamp(1e-3, 1.0);
+        self
+    }
+
+    // Spread of the light around the fiber, in (0, 1].
+    #[inline]
+    pub fn with_azimuthal_roughness(mut self, roughness: float) -> Self {
+        self.azimuthal_roughness = roughness.clamp(1e-3, 1.0);
+        self
+    }
+
+    // Tilt of the cuticle scales in degrees, which shifts the highlights.
+    #[inline]
+    pub fn with_scale_angle(mut self, degrees: float) -> Self {
+        self.scale_angle = degrees;
+        self
+    }
+
+    #[inline]
+    pub fn with_index_of_refraction(mut self, index_of_refraction: float) -> Self {
+        self.index_of_refraction = index_of_refraction;
+        self
+    }
+
+    fn absorption(&self) -> Color {
+        match self.pigment {
+            Pigment::Absorption(absorption) => absorption,
+            Pigment::Reflectance(color) => {
+                let b = self.azimuthal_roughness;
+                let scale = 5.969 - 0.215 * b + 2.532 * b.powi(2) - 10.73 * b.powi(3)
+                    + 5.574 * b.powi(4)
+                    + 0.245 * b.powi(5);
+                color.map(|c| (c.clamp(1e-4, 1.0).ln() / scale).powi(2))
+            }
+        }
+    }
+}
+
+impl Material for Hair {
+    fn scatter(
+        &self,
+        in_ray: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut SampleContext,
+    ) -> Option<(Color, Ray)> {
+        // Local frame with x along the fiber and z facing the ray, where v
+        // across the fiber gives the offset h along y.
+        let frame = Frame::from_normal_tangent(&hit_record.geometric_normal, &hit_record.dpdu);
+        let h = (2.0 * hit_record.v - 1.0).clamp(-1.0, 1.0);
+        let wo = frame.to_local(&-in_ray.direction().unit());
+        let fiber = Fiber::new(self, h, wo.x());
+        let phi_o = wo.z().atan2(wo.y());
+
+        // Pick a lobe by its share of the light, then sample its longitudinal
+        // and azimuthal parts.
+        let mut choice = sampler.next_1d();
+        let mut p = 0;
+        while p < LOBES && choice >= fiber.pdf[p] {
+            choice -= fiber.pdf[p];
+            p += 1;
+        }
+        let (sin_theta_op, cos_theta_op) = fiber.tilted(p, wo.x());
+        let [u, u_phi] = sampler.next_2d();
+        let u = u.max(1e-5);
+        let v = fiber.variance[p];
+        let cos_theta = 1.0 + v * (u + (1.0 - u) * (-2.0 / v).exp()).ln();
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let cos_phi = (2.0 * PI * u_phi).cos();
+        let sin_theta_i =
+            (-cos_theta * sin_theta_op + sin_theta * cos_phi * cos_theta_op).clamp(-1.0, 1.0);
+        let cos_theta_i = (1.0 - sin_theta_i * sin_theta_i).max(0.0).sqrt();
+        let dphi = if p < LOBES {
+            fiber.phi(p) + sample_trimmed_logistic(sampler.next_1d(), fiber.s)
+        } else {
+            2.0 * PI * sampler.next_1d()
+        };
+        let phi_i = phi_o + dphi;
+        let wi = Vec3::new(
+            sin_theta_i,
+            cos_theta_i * phi_i.cos(),
+            cos_theta_i * phi_i.sin(),
+        );
+
+        let (value, pdf) = fiber.evaluate(wo.x(), sin_theta_i, dphi);
+        if pdf <= 0.0 {
+            return None;
+        }
+        return Some((
+            value / pdf,
+            in_ray.spawn(hit_record.position, frame.to_world(&wi)),
+        ));
+    }
+}
+
+// The lobes of a hair for one outgoing direction.
+struct Fiber {
+    gamma_o: float,
+    gamma_t: float,
+    // Longitudinal variance and azimuthal scale from the roughnesses.
+    variance: [float; LOBES + 1],
+    s: float,
+    // Sine and cosine of 2^k times the scale angle.
+    sin_2k_alpha: [float; 3],
+    cos_2k_alpha: [float; 3],
+    attenuation: [Color; LOBES + 1],
+    pdf: [float; LOBES + 1],
+}
+
+impl Fiber {
+    fn new(hair: &Hair, h: float, sin_theta_o: float) -> Self {
+        let eta = hair.index_of_refraction;
+        let cos_theta_o = (1.0 - sin_theta_o * sin_theta_o).max(1e-4).sqrt();
+
+        let beta_m = hair.longitudinal_roughness;
+        let v0 = (0.726 * beta_m + 0.812 * beta_m.powi(2) + 3.7 * beta_m.powi(20)).powi(2);
+        let variance = [v0, 0.25 * v0, 4.0 * v0, 4.0 * v0];
+        let beta_n = hair.azimuthal_roughness;
+        let s =
+            (PI / 8.0).sqrt() * (0.265 * beta_n + 1.194 * beta_n.powi(2) + 5.372 * beta_n.powi(22));
+
+        let mut sin_2k_alpha = [hair.scale_angle.to_radians().sin(), 0.0, 0.0];
+        let mut cos_2k_alpha = [(1.0 - sin_2k_alpha[0].powi(2)).max(0.0).sqrt(), 0.0, 0.0];
+        for i in 1..3 {
+            sin_2k_alpha[i] = 2.0 * cos_2k_alpha[i - 1] * sin_2k_alpha[i - 1];
+            cos_2k_alpha[i] = cos_2k_alpha[i - 1].powi(2) - sin_2k_alpha[i - 1].powi(2);
+        }
+
+        // Refraction into the fiber, projected onto its cross section.
+        let sin_theta_t = sin_theta_o / eta;
+        let cos_theta_t = (1.0 - sin_theta_t * sin_theta_t).max(0.0).sqrt();
+        let eta_projected = (eta * eta - sin_theta_o * sin_theta_o).sqrt() / cos_theta_o;
+        let sin_gamma_t = h / eta_projected;
+        let cos_gamma_t = (1.0 - sin_gamma_t * sin_gamma_t).max(0.0).sqrt();
+        let transmittance =
+            (-hair.absorption() * (2.0 * cos_gamma_t / cos_theta_t)).map(float::exp);
+
+        let cos_gamma_o = (1.0 - h * h).max(0.0).sqrt();
+        let fresnel = fresnel_dielectric(cos_theta_o * cos_gamma_o, eta);
+        let mut attenuation = [Color::uniform(fresnel); LOBES + 1];
+        attenuation[1] = (1.0 - fresnel).powi(2) * transmittance;
+        for p in 2..LOBES {
+            attenuation[p] = attenuation[p - 1] * transmittance * fresnel;
+        }
+        let rest = attenuation[LOBES - 1] * transmittance * fresnel;
+        attenuation[LOBES] = rest / (1.0 - transmittance * fresnel);
+
+        let total: float = attenuation.iter().map(Color::luminance).sum();
+        let pdf = attenuation.map(|a| {
+            if total > 0.0 {
+                a.luminance() / total
+            } else {
+                0.25
+            }
+        });
+
+        Self {
+            gamma_o: h.asin(),
+            gamma_t: sin_gamma_t.clamp(-1.0, 1.0).asin(),
+            variance,
+            s,
+            sin_2k_alpha,
+            cos_2k_alpha,
+            attenuation,
+            pdf,
+        }
+    }
+
+    // The outgoing angle as seen by lobe `p`, rotated by the scales.
+    fn tilted(&self, p: usize, sin_theta_o: float) -> (float, float) {
+        let cos_theta_o = (1.0 - sin_theta_o * sin_theta_o).max(0.0).sqrt();
+        let (index, sign) = match p {
+            0 => (1, -1.0),
+            1 => (0, 1.0),
+            2 => (2, 1.0),
+            _ => return (sin_theta_o, cos_theta_o),
+        };
+        let (sin, cos) = (sign * self.sin_2k_alpha[index], self.cos_2k_alpha[index]);
+        return (
+            sin_theta_o * cos + cos_theta_o * sin,
+            (cos_theta_o * cos - sin_theta_o * sin).abs(),
+        );
+    }
+
+    // Azimuthal exit angle of the center of lobe `p`.
+    #[inline]
+    fn phi(&self, p: usize) -> float {
+        let p = p as float;
+        2.0 * p * self.gamma_t - 2.0 * self.gamma_o + p * PI
+    }
+
+    // The BSDF times the cosine term and the sampling pdf, for an incoming
+    // direction at `sin_theta_i` and `dphi` around the fiber from the
+    // outgoing one.
+    fn evaluate(&self, sin_theta_o: float, sin_theta_i: float, dphi: float) -> (Color, float) {
+        let cos_theta_i = (1.0 - sin_theta_i * sin_theta_i).max(0.0).sqrt();
+        let mut value = Color::ZERO;
+        let mut pdf = 0.0;
+        for p in 0..=LOBES {
+            let (sin_theta_op, cos_theta_op) = self.tilted(p, sin_theta_o);
+            let longitudinal = longitudinal_scattering(
+                cos_theta_i,
+                cos_theta_op,
+                sin_theta_i,
+                sin_theta_op,
+                self.variance[p],
+            );
+            let azimuthal = if p < LOBES {
+                let mut offset = dphi - self.phi(p);
+                offset -= 2.0 * PI * (offset / (2.0 * PI)).round();
+                trimmed_logistic(offset, self.s)
+            } else {
+                1.0 / (2.0 * PI)
+            };
+            value += longitudinal * azimuthal * self.attenuation[p];
+            pdf += longitudinal * azimuthal * self.pdf[p];
+        }
+        return (value, pdf);
+    }
+}
+
+// The longitudinal lobe M_p of d'Eon et al., with their stable evaluation for
+// low variances.
+fn longitudinal_scattering(
+    cos_theta_i: float,
+    cos_theta_o: float,
+    sin_theta_i: float,
+    sin_theta_o: float,
+    v: float,
+) -> float {
+    let a = cos_theta_i * cos_theta_o / v;
+    let b = sin_theta_i * sin_theta_o / v;
+    if v <= 0.1 {
+        return (log_bessel_i0(a) - b - 1.0 / v + std::f32::consts::LN_2 + (1.0 / (2.0 * v)).ln())
+            .exp();
+    }
+    return (-b).exp() * bessel_i0(a) / ((1.0 / v).sinh() * 2.0 * v);
+}
+
+// Modified Bessel function of the first kind, by its power series.
+fn bessel_i0(x: float) -> float {
+    let mut value = 0.0;
+    let mut x2i = 1.0;
+    let mut factorial: float = 1.0;
+    let mut four_i = 1.0;
+    for i in 0..10 {
+        if i > 1 {
+            factorial *= i as float;
+        }
+        value += x2i / (four_i * factorial * factorial);
+        x2i *= x * x;
+        four_i *= 4.0;
+    }
+    return value;
+}
+
+fn log_bessel_i0(x: float) -> float {
+    if x > 12.0 {
+        return x + 0.5 * (-(2.0 * PI).ln() + (1.0 / x).ln() + 1.0 / (8.0 * x));
+    }
+    return bessel_i0(x).ln();
+}
+
+#[inline]
+fn logistic_cdf(x: float, s: float) -> float {
+    1.0 / (1.0 + (-x / s).exp())
+}
+
+// The logistic distribution restricted to [-pi, pi].
+fn trimmed_logistic(x: float, s: float) -> float {
+    let e = (-x.abs() / s).exp();
+    let logistic = e / (s * (1.0 + e) * (1.0 + e));
+    return logistic / (logistic_cdf(PI, s) - logistic_cdf(-PI, s));
+}
+
+fn sample_trimmed_logistic(u: float, s: float) -> float {
+    let k = logistic_cdf(PI, s) - logistic_cdf(-PI, s);
+    let x = -s * (1.0 / (u * k + logistic_cdf(-PI, s)) - 1.0).ln();
+    return x.clamp(-PI, PI);
+}
@@ -0,0 +1,455 @@
+use std::rc::Rc;
+
+use crate::{
+    aabb::Aabb,
+    hittable::{HitRecord, Hittable},
+    image::Image,
+    interval::Interval,
+    material::Material,
+    prelude::*,
+};
+
+// Terrain from a grid of heights in [0, 1], stretched over the box from
+// `origin` to `origin + size` with Y up. Each grid cell is split into two
+// triangles, shaded with normals interpolated from the heights around each
+// vertex. Rays walk the grid with a 2D DDA over mip levels of the min/max
+// heights of blocks of cells, stepping over whole blocks the ray passes above
+// or below, so they only test triangles near the surface.
+pub struct Heightfield {
+    resolution: [usize; 2],
+    heights: Vec<float>,
+    normals: Vec<Vec3>,
+    // Min/max heights over blocks of 2^level cells per side, finest first.
+    levels: Vec<Vec<Interval>>,
+    origin: Point3,
+    size: Vec3,
+    material: Rc<dyn Material>,
+}
+
+impl Heightfield {
+    // Samples `height` at every grid vertex, given its position along X and Z
+    // in [0, 1].
+    pub fn from_fn(
+        resolution: [usize; 2],
+        height: impl Fn(float, float) -> float,
+        origin: Point3,
+        size: Vec3,
+        material: Rc<dyn Material>,
+    ) -> Self {
+        let [nx, nz] = resolution;
+        assert!(nx >= 2 && nz >= 2, "heightfields need at least 2x2 samples");
+        let heights = (0..nx * nz)
+            .map(|i| {
+                let (x, z) = (i % nx, i / nx);
+                height(
+                    x as float / (nx - 1) as float,
+                    z as float / (nz - 1) as float,
+                )
+            })
+            .collect();
+
+        let mut heightfield = Self {
+            resolution,
+            heights,
+            normals: vec![],
+            levels: vec![],
+            origin,
+            size,
+            material,
+        };
+        heightfield.normals = (0..nx * nz)
+            .map(|i| heightfield.vertex_normal(i % nx, i / nx))
+            .collect();
+        heightfield.build_levels();
+        return heightfield;
+    }
+
+    // Uses the brightness of each pixel as its height, with the image's top
+    // row at the low Z edge, matching the UVs of `ImageTexture`. Load the
+    // image without gamma decoding.
+    pub fn from_image(
+        image: &Image,
+        origin: Point3,
+        size: Vec3,
+        material: Rc<dyn Material>,
+    ) -> Self {
+        let (width, height) = (image.width(), image.height());
+        Self::from_fn(
+            [width, height],
+            |x, z| {
+                let pixel = image.pixel(
+                    (x * (width - 1) as float).round() as usize,
+                    (z * (height - 1) as float).round() as usize,
+                );
+                (pixel.r() + pixel.g() + pixel.b()) / 3.0
+            },
+            origin,
+            size,
+            material,
+        )
+    }
+
+    #[inline]
+    fn cell_size(&self) -> (float, float) {
+        let [nx, nz] = self.resolution;
+        (
+            self.size.x() / (nx - 1) as float,
+            self.size.z() / (nz - 1) as float,
+        )
+    }
+
+    #[inline]
+    fn height(&self, x: usize, z: usize) -> float {
+        self.heights[z * self.resolution[0] + x]
+    }
+
+    #[inline]
+    fn vertex(&self, x: usize, z: usize) -> Point3 {
+        let (dx, dz) = self.cell_size();
+        self.origin
+            + Vec3::new(
+                x as float * dx,
+                self.height(x, z) * self.size.y(),
+                z as float * dz,
+            )
+    }
+
+    // Central differences of the heights, one sided at the edges.
+    fn vertex_normal(&self, x: usize, z: usize) -> Vec3 {
+        let [nx, nz] = self.resolution;
+        let (dx, dz) = self.cell_size();
+        let (x0, x1) = (x.saturating_sub(1), (x + 1).min(nx - 1));
+        let (z0, z1) = (z.saturating_sub(1), (z + 1).min(nz - 1));
+        let slope_x =
+            (self.height(x1, z) - self.height(x0, z)) * self.size.y() / ((x1 - x0) as float * dx);
+        let slope_z =
+            (self.height(x, z1) - self.height(x, z0)) * self.size.y() / ((z1 - z0) as float * dz);
+        return Vec3::new(-slope_x, 1.0, -slope_z).unit();
+    }
+
+    fn build_levels(&mut self) {
+        let [nx, nz] = self.resolution;
+        let mut size = [nx - 1, nz - 1];
+        let mut level: Vec<Interval> = (0..size[0] * size[1])
+            .map(|i| {
+                let (x, z) = (i % size[0], i / size[0]);
+                [(x, z), (x + 1, z), (x, z + 1), (x + 1, z + 1)]
+                    .iter()
+                    .map(|&(x, z)| self.height(x, z))
+                    .fold(Interval::EMPTY, |range, h| {
+                        range.union(&Interval::new(h, h))
+                    })
+            })
+            .collect();
+
+        loop {
+            self.levels.push(level);
+            if size == [1, 1] {
+                break;
+            }
+            let previous = self.levels.last().unwrap();
+            let next_size = [size[0].div_ceil(2), size[1].div_ceil(2)];
+            level = (0..next_size[0] * next_size[1])
+                .map(|i| {
+                    let (x, z) = (2 * (i % next_size[0]), 2 * (i / next_size[0]));
+                    [(x, z), (x + 1, z), (x, z + 1), (x + 1, z + 1)]
+                        .iter()
+                        .filter(|&&(x, z)| x < size[0] && z < size[1])
+                        .fold(Interval::EMPTY, |range, &(x, z)| {
+                            range.union(&previous[z * size[0] + x])
+                        })
+                })
+                .collect();
+            size = next_size;
+        }
+    }
+
+    // Bounds of the node at `level` covering cells from (x, z) on.
+    fn node_bounds(&self, level: usize, x: usize, z: usize) -> Aabb {
+        let [nx, nz] = self.resolution;
+        let (dx, dz) = self.cell_size();
+        let cells = 1 << level;
+        let width = self.level_size(level)[0];
+        let range = self.levels[level][z * width + x];
+        let (x0, x1) = (x * cells, ((x + 1) * cells).min(nx - 1));
+        let (z0, z1) = (z * cells, ((z + 1) * cells).min(nz - 1));
+        Aabb::new(
+            Interval::new(
+                self.origin.x() + x0 as float * dx,
+                self.origin.x() + x1 as float * dx,
+            ),
+            Interval::new(
+                self.origin.y() + range.min * self.size.y(),
+                self.origin.y() + range.max * self.size.y(),
+            )
+            .expand(1e-4),
+            Interval::new(
+                self.origin.z() + z0 as float * dz,
+                self.origin.z() + z1 as float * dz,
+            ),
+        )
+    }
+
+    #[inline]
+    fn level_size(&self, level: usize) -> [usize; 2] {
+        let [nx, nz] = self.resolution;
+        let cells = 1 << level;
+        [(nx - 1).div_ceil(cells), (nz - 1).div_ceil(cells)]
+    }
+
+    // Steps through the nodes along the ray in order, a level finer wherever
+    // the ray's height over a node overlaps its heights and a level coarser
+    // whenever it crosses into the next parent. The first cell hit holds the
+    // closest hit, since its triangles lie within the cell.
+    fn traverse(&self, ray: &Ray, mut t_interval: Interval) -> Option<HitRecord> {
+        let top = self.levels.len() - 1;
+        let span = self.node_bounds(top, 0, 0).hit(ray, t_interval)?;
+        let (dx, dz) = self.cell_size();
+        let direction = ray.direction();
+        let step = [direction.x(), direction.z()].map(|d| if d < 0.0 { -1 } else { 1 });
+        // Position along each axis in cells at time t.
+        let cells_at = |t: float| {
+            let p = ray.at(t) - self.origin;
+            [p.x() / dx, p.z() / dz]
+        };
+
+        let (mut level, mut cell, mut t_enter) = (top, [0isize, 0isize], span.min);
+        loop {
+            let size = self.level_size(level);
+            if (0..2).any(|a| cell[a] < 0 || cell[a] >= size[a] as isize) {
+                return None;
+            }
+            let (x, z) = (cell[0] as usize, cell[1] as usize);
+            let bounds = self.node_bounds(level, x, z);
+
+            // The ray leaves the node through the nearer of its far sides.
+            let t_exits = [(0, direction.x()), (2, direction.z())].map(|(axis, d)| {
+                let side = bounds.axis(axis);
+                let edge = if d < 0.0 { side.min } else { side.max };
+                if d == 0.0 {
+                    float::INFINITY
+                } else {
+                    (edge - ray.origin().e[axis]) / d
+                }
+            });
+            let exit_axis = (t_exits[1] < t_exits[0]) as usize;
+            let t_exit = t_exits[exit_axis].min(span.max);
+
+            let (y_enter, y_exit) = (ray.at(t_enter).y(), ray.at(t_exit).y());
+            let heights = bounds.axis(1);
+            if y_enter.min(y_exit) <= heights.max && y_enter.max(y_exit) >= heights.min {
+                if level == 0 {
+                    let hit = self.hit_cell(ray, x, z, &mut t_interval);
+                    if hit.is_some() {
+                        return hit;
+                    }
+                } else {
+                    // Descend into the child the ray enters the node through.
+                    level -= 1;
+                    let position = cells_at(t_enter);
+                    let child_size = self.level_size(level);
+                    for a in 0..2 {
+                        let middle = ((2 * cell[a] + 1) << level) as float;
+                        let upper = position[a] > middle || (position[a] == middle && step[a] > 0);
+                        let child = 2 * cell[a] + upper as isize;
+                        cell[a] = child.min(child_size[a] as isize - 1);
+                    }
+                    continue;
+                }
+            }
+
+            if t_exit >= span.max || t_exit > t_interval.max {
+                return None;
+            }
+            let mut previous = cell;
+            cell[exit_axis] += step[exit_axis];
+            t_enter = t_exit;
+            // Go back up for as long as the step crossed into another parent.
+            while level < top && (0..2).any(|a| cell[a] >> 1 != previous[a] >> 1) {
+                level += 1;
+                cell = cell.map(|c| c >> 1);
+                previous = previous.map(|c| c >> 1);
+            }
+        }
+    }
+
+    fn hit_cell(
+        &self,
+        ray: &Ray,
+        x: usize,
+        z: usize,
+        t_interval: &mut Interval,
+    ) -> Option<HitRecord> {
+        let corners = [(x, z), (x + 1, z + 1), (x + 1, z), (x, z + 1)];
+        let mut closest = None;
+        for triangle in [
+            [corners[0], corners[3], corners[1]],
+            [corners[0], corners[1], corners[2]],
+        ] {
+            let [a, b, c] = triangle.map(|(x, z)| self.vertex(x, z));
+            let Some((t, beta, gamma)) = intersect_triangle(ray, &a, &b, &c) else {
+                continue;
+            };
+            if !t_interval.surrounds(t) {
+                continue;
+            }
+            *t_interval = t_interval.with_max(t);
+            closest = Some(self.record(ray, t, triangle, [1.0 - beta - gamma, beta, gamma]));
+        }
+        return closest;
+    }
+
+    fn record(
+        &self,
+        ray: &Ray,
+        t: float,
+        triangle: [(usize, usize); 3],
+        weights: [float; 3],
+    ) -> HitRecord {
+        let [nx, nz] = self.resolution;
+        let [a, b, c] = triangle.map(|(x, z)| self.vertex(x, z));
+        let interpolate = |f: &dyn Fn(usize, usize) -> Vec3| {
+            (0..3)
+                .map(|i| weights[i] * f(triangle[i].0, triangle[i].1))
+                .sum::<Vec3>()
+        };
+        let normal = interpolate(&|x, z| self.normals[z * nx + x]).unit();
+        let grid = interpolate(&|x, z| Vec3::new(x as float, 0.0, z as float));
+        let (u, v) = (
+            grid.x() / (nx - 1) as float,
+            1.0 - grid.z() / (nz - 1) as float,
+        );
+
+        // Project the grid axes onto the smooth surface for the tangents.
+        let slope_x = -normal.x() / normal.y();
+        let slope_z = -normal.z() / normal.y();
+        let dpdu = self.size.x() * Vec3::new(1.0, slope_x, 0.0);
+        let dpdv = -self.size.z() * Vec3::new(0.0, slope_z, 1.0);
+
+        let geometric_normal = (b - a).cross(&(c - a)).unit();
+        let mut hit_record = HitRecord::from_outward_normal(
+            t,
+            ray.at(t),
+            self.material.clone(),
+            geometric_normal,
+            ray,
+        )
+        .with_uv(u, v)
+        .with_tangents(dpdu, dpdv);
+        hit_record.set_outward_shading_normal(normal);
+        return hit_record;
+    }
+}
+
+impl Hittable for Heightfield {
+    fn hit(&self, ray: &Ray, t_interval: Interval) -> Option<HitRecord> {
+        return self.traverse(ray, t_interval);
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.node_bounds(self.levels.len() - 1, 0, 0)
+    }
+}
+
+// Möller-Trumbore ray/triangle intersection, returning the ray parameter and
+// the barycentric weights of `b` and `c`.
+fn intersect_triangle(
+    ray: &Ray,
+    a: &Point3,
+    b: &Point3,
+    c: &Point3,
+) -> Option<(float, float, float)> {
+    let (edge_1, edge_2) = (b - a, c - a);
+    let p = ray.direction().cross(&edge_2);
+    let determinant = edge_1.dot(&p);
+    if determinant.abs() < 1e-12 {
+        return None;
+    }
+    let inverse = 1.0 / determinant;
+    let to_origin = ray.origin() - a;
+    let beta = to_origin.dot(&p) * inverse;
+    if !(0.0..=1.0).contains(&beta) {
+        return None;
+    }
+    let q = to_origin.cross(&edge_1);
+    let gamma = ray.direction().dot(&q) * inverse;
+    if gamma < 0.0 || beta + gamma > 1.0 {
+        return None;
+    }
+    return Some((edge_2.dot(&q) * inverse, beta, gamma));
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::*;
+    use crate::material::Lambertian;
+
+    // Tests every triangle of the grid.
+    fn brute_force(
+        heightfield: &Heightfield,
+        ray: &Ray,
+        mut t_interval: Interval,
+    ) -> Option<float> {
+        let [nx, nz] = heightfield.resolution;
+        let mut closest = None;
+        for z in 0..nz - 1 {
+            for x in 0..nx - 1 {
+                if let Some(hit) = heightfield.hit_cell(ray, x, z, &mut t_interval) {
+                    closest = Some(hit.t);
+                }
+            }
+        }
+        return closest;
+    }
+
+    #[test]
+    fn traversal_finds_the_closest_triangle() {
+        let material = Rc::new(Lambertian::new(Color::uniform(0.5)));
+        // Odd sizes leave partial blocks at the far edges of the coarser levels.
+        let heightfield = Heightfield::from_fn(
+            [21, 14],
+            |x, z| 0.5 + 0.3 * (9.0 * x).sin() * (7.0 * z).cos() + 0.2 * (23.0 * x * z).sin(),
+            Point3::new(-2.0, -0.5, -1.0),
+            Vec3::new(4.0, 1.0, 3.0),
+            material,
+        );
+
+        let mut rng = StdRng::seed_from_u64(42);
+        let mut hits = 0;
+        for i in 0..2000 {
+            let origin = Point3::new(
+                rng.gen_range(-3.0..3.0),
+                rng.gen_range(-1.0..1.5),
+                rng.gen_range(-2.0..3.0),
+            );
+            // Aimed at points within the terrain's box, or straight down.
+            let target = Point3::new(
+                rng.gen_range(-2.0..2.0),
+                rng.gen_range(-0.5..0.5),
+                rng.gen_range(-1.0..2.0),
+            );
+            let direction = if i % 4 == 0 {
+                Vec3::NEG_Y
+            } else {
+                target - origin
+            };
+            let ray = Ray::new(origin, direction);
+            let t_interval = Interval::new(0.001, float::INFINITY);
+
+            let expected = brute_force(&heightfield, &ray, t_interval);
+            let found = heightfield.hit(&ray, t_interval).map(|hit| hit.t);
+            match (found, expected) {
+                (Some(found), Some(expected)) => {
+                    assert!((found - expected).abs() < 1e-4, "{:?}", ray);
+                    hits += 1;
+                }
+                (None, None) => {}
+                _ => panic!("{:?} found {:?}, expected {:?}", ray, found, expected),
+            }
+        }
+        // Enough of the rays hit for the comparison to mean something.
+        assert!(hits > 1000, "{}", hits);
+    }
+}
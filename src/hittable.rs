@@ -1,14 +1,23 @@
 use std::rc::Rc;
 
-use crate::{interval::Interval, material::Material, prelude::*};
+use crate::{
+    aabb::Aabb, interval::Interval, material::Material, prelude::*, sampler::SampleContext,
+};
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct HitRecord {
     pub t: float,
     pub position: Point3,
     pub material: Rc<dyn Material>,
+    // Shading normal, which materials may perturb. Both normals face against
+    // the incoming ray.
     pub normal: Vec3,
+    pub geometric_normal: Vec3,
     pub front_face: bool,
+    pub u: float,
+    pub v: float,
+    pub dpdu: Vec3,
+    pub dpdv: Vec3,
 }
 
 impl HitRecord {
@@ -20,20 +29,76 @@ impl HitRecord {
         ray: &Ray,
     ) -> Self {
         let front_face = ray.direction().dot(&outward_normal) < 0.0;
+        let normal = if front_face {
+            outward_normal
+        } else {
+            -outward_normal
+        };
         Self {
             t,
             position,
             material,
             front_face,
-            normal: if front_face {
-                outward_normal
-            } else {
-                -outward_normal
-            },
+            normal,
+            geometric_normal: normal,
+            u: 0.0,
+            v: 0.0,
+            dpdu: Vec3::ZERO,
+            dpdv: Vec3::ZERO,
         }
     }
+
+    #[inline]
+    pub fn with_uv(mut self, u: float, v: float) -> Self {
+        self.u = u;
+        self.v = v;
+        self
+    }
+
+    #[inline]
+    pub fn with_tangents(mut self, dpdu: Vec3, dpdv: Vec3) -> Self {
+        self.dpdu = dpdu;
+        self.dpdv = dpdv;
+        self
+    }
+
+    #[inline]
+    pub fn outward_normal(&self) -> Vec3 {
+        if self.front_face {
+            self.normal
+        } else {
+            -self.normal
+        }
+    }
+
+    // Replaces the shading normal given one that points out of the surface.
+    #[inline]
+    pub fn set_outward_shading_normal(&mut self, outward_normal: Vec3) {
+        self.normal = if self.front_face {
+            outward_normal
+        } else {
+            -outward_normal
+        };
+    }
 }
 
 pub trait Hittable {
     fn hit(&self, ray: &Ray, t_interval: Interval) -> Option<HitRecord>;
+
+    // Samples the first collision of `ray` within `t_interval` with the
+    // participating media in the object, as a record whose material is the
+    // phase function. Media are not surfaces and never `hit`; `ray_color`
+    // samples their free flights up to the next surface instead.
+    fn sample_medium(
+        &self,
+        _ray: &Ray,
+        _t_interval: Interval,
+        _sampler: &mut SampleContext,
+    ) -> Option<HitRecord> {
+        None
+    }
+
+    // Must enclose the object at every time it can be hit, so moving objects
+    // report the union of their bounds over their whole motion.
+    fn bounding_box(&self) -> Aabb;
 }
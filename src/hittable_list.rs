@@ -1,16 +1,27 @@
-use crate::{hittable::Hittable, interval::Interval, prelude::*};
+use crate::{
+    aabb::Aabb,
+    hittable::{HitRecord, Hittable},
+    interval::Interval,
+    prelude::*,
+    sampler::SampleContext,
+};
 
 #[derive(Default)]
 pub struct HittableList {
     list: Vec<Box<dyn Hittable>>,
+    bounding_box: Aabb,
 }
 
 impl HittableList {
     pub fn new() -> Self {
-        Self { list: vec![] }
+        Self {
+            list: vec![],
+            bounding_box: Aabb::EMPTY,
+        }
     }
 
     pub fn add(&mut self, hittable: Box<dyn Hittable>) {
+        self.bounding_box = self.bounding_box.union(&hittable.bounding_box());
         self.list.push(hittable);
     }
 }
@@ -26,4 +37,25 @@ impl Hittable for HittableList {
         }
         return hit_record;
     }
+
+    // Every medium collides independently, so the nearest collision wins.
+    fn sample_medium(
+        &self,
+        ray: &Ray,
+        mut t_interval: Interval,
+        sampler: &mut SampleContext,
+    ) -> Option<HitRecord> {
+        let mut collision = None;
+        for i in &self.list {
+            if let Some(hit) = i.sample_medium(ray, t_interval, sampler) {
+                t_interval = t_interval.with_max(hit.t);
+                collision = Some(hit);
+            }
+        }
+        return collision;
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bounding_box
+    }
 }
@@ -0,0 +1,229 @@
+use std::{
+    error::Error,
+    fmt::Debug,
+    fs::File,
+    hash::{Hash, Hasher},
+    io::{BufReader, BufWriter, Write},
+    path::Path,
+};
+
+use crate::prelude::*;
+
+pub type ImageResult<T> = Result<T, Box<dyn Error>>;
+
+// Pixels are stored row by row from the top-left corner.
+pub struct Image {
+    width: usize,
+    height: usize,
+    pixels: Vec<Color>,
+}
+
+impl Image {
+    pub fn new(width: usize, height: usize, pixels: Vec<Color>) -> Self {
+        assert_eq!(pixels.len(), width * height, "pixel count must match size");
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    // Loads a gamma encoded color image, e.g. an albedo map.
+    pub fn load(path: impl AsRef<Path>) -> ImageResult<Self> {
+        let mut image = Self::load_raw(path)?;
+        for pixel in &mut image.pixels {
+            *pixel = pixel.gamma_decoded();
+        }
+        return Ok(image);
+    }
+
+    // Loads an image without gamma decoding, for data such as normal or
+    // height maps.
+    pub fn load_raw(path: impl AsRef<Path>) -> ImageResult<Self> {
+        let path = path.as_ref();
+        let image = match path.extension().and_then(|e| e.to_str()) {
+            Some(e) if e.eq_ignore_ascii_case("png") => Self::read_png(path)?,
+            Some(e) if e.eq_ignore_ascii_case("ppm") => Self::read_ppm(path)?,
+            _ => return Err(format!("unsupported image format: {}", path.display()).into()),
+        };
+        // Textures wrap and clamp lookups into the image, which needs a pixel.
+        if image.width == 0 || image.height == 0 {
+            return Err(format!("image has no pixels: {}", path.display()).into());
+        }
+        return Ok(image);
+    }
+
+    fn read_png(path: impl AsRef<Path>) -> ImageResult<Self> {
+        let mut decoder = png::Decoder::new(BufReader::new(File::open(path)?));
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+        let mut reader = decoder.read_info()?;
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buffer)?;
+
+        let samples = info.color_type.samples();
+        let pixels = buffer[..info.buffer_size()]
+            .chunks_exact(samples)
+            .map(|p| match info.color_type {
+                png::ColorType::Grayscale | png::ColorType::GrayscaleAlpha => {
+                    Color::uniform(p[0] as float)
+                }
+                _ => Color::new(p[0] as float, p[1] as float, p[2] as float),
+            })
+            .map(|c| c / 255.0)
+            .collect();
+        return Ok(Self::new(info.width as usize, info.height as usize, pixels));
+    }
+
+    // Reads both the ASCII (P3) and binary (P6) variants.
+    fn read_ppm(path: impl AsRef<Path>) -> ImageResult<Self> {
+        let data = std::fs::read(path)?;
+        let mut cursor = 0;
+        let magic = next_ppm_token(&data, &mut cursor)?;
+        let width: usize = next_ppm_token(&data, &mut cursor)?.parse()?;
+        let height: usize = next_ppm_token(&data, &mut cursor)?.parse()?;
+        let max_value: u32 = next_ppm_token(&data, &mut cursor)?.parse()?;
+        if !(1..=65535).contains(&max_value) {
+            return Err(format!("PPM maximum value out of range: {}", max_value).into());
+        }
+        let max_value = max_value as float;
+
+        let count = width
+            .checked_mul(height)
+            .and_then(|count| count.checked_mul(3))
+            .ok_or("PPM image is too large")?;
+        let samples: Vec<float> = match magic.as_str() {
+            "P3" => (0..count)
+                .map(|_| Ok(next_ppm_token(&data, &mut cursor)?.parse()?))
+                .collect::<ImageResult<_>>()?,
+            "P6" => {
+                let raster = data.get(cursor + 1..).unwrap_or_default();
+                if max_value < 256.0 {
+                    raster.iter().take(count).map(|&b| b as float).collect()
+                } else {
+                    raster
+                        .chunks_exact(2)
+                        .take(count)
+                        .map(|b| u16::from_be_bytes([b[0], b[1]]) as float)
+                        .collect()
+                }
+            }
+            _ => return Err(format!("unsupported PPM variant: {}", magic).into()),
+        };
+        if samples.len() != count {
+            return Err("PPM raster is truncated".into());
+        }
+
+        let pixels = samples
+            .chunks_exact(3)
+            .map(|p| Color::new(p[0], p[1], p[2]) / max_value)
+            .collect();
+        return Ok(Self::new(width, height, pixels));
+    }
+
+    // Saves a linear color image gamma encoded, as PNG or binary PPM by the
+    // extension of `path`.
+    pub fn save(&self, path: impl AsRef<Path>) -> ImageResult<()> {
+        let encoded = self.pixels.iter().map(|p| p.gamma_corrected()).collect();
+        return Self::new(self.width, self.height, encoded).save_raw(path);
+    }
+
+    // Saves the pixels as they are, for data such as sample counts.
+    pub fn save_raw(&self, path: impl AsRef<Path>) -> ImageResult<()> {
+        let path = path.as_ref();
+        let bytes: Vec<u8> = self
+            .pixels
+            .iter()
+            .flat_map(|p| p.e.map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8))
+            .collect();
+        let mut output = BufWriter::new(File::create(path)?);
+        match path.extension().and_then(|e| e.to_str()) {
+            Some(e) if e.eq_ignore_ascii_case("png") => {
+                let mut encoder = png::Encoder::new(output, self.width as u32, self.height as u32);
+                encoder.set_color(png::ColorType::Rgb);
+                encoder.set_depth(png::BitDepth::Eight);
+                encoder.write_header()?.write_image_data(&bytes)?;
+            }
+            Some(e) if e.eq_ignore_ascii_case("ppm") => {
+                write!(output, "P6\n{} {}\n255\n", self.width, self.height)?;
+                output.write_all(&bytes)?;
+                output.flush()?;
+            }
+            _ => return Err(format!("unsupported image format: {}", path.display()).into()),
+        }
+        return Ok(());
+    }
+
+    #[inline]
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    #[inline]
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    #[inline]
+    pub fn pixel(&self, x: usize, y: usize) -> Color {
+        self.pixels[y * self.width + x]
+    }
+
+    // Copies `image` over this one with its top left corner at (x, y), e.g. to
+    // stitch tiles back into a whole frame. Parts outside are cut off.
+    pub fn paste(&mut self, x: usize, y: usize, image: &Image) {
+        let width = image.width.min(self.width.saturating_sub(x));
+        let height = image.height.min(self.height.saturating_sub(y));
+        for row in 0..height {
+            let source = row * image.width;
+            let target = (y + row) * self.width + x;
+            self.pixels[target..target + width]
+                .copy_from_slice(&image.pixels[source..source + width]);
+        }
+    }
+}
+
+impl Debug for Image {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Image")
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .finish_non_exhaustive()
+    }
+}
+
+// Hashes every texel, e.g. for the scene id of checkpoints.
+impl Hash for Image {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.width.hash(state);
+        self.height.hash(state);
+        for pixel in &self.pixels {
+            for value in pixel.e {
+                value.to_bits().hash(state);
+            }
+        }
+    }
+}
+
+fn next_ppm_token(data: &[u8], cursor: &mut usize) -> ImageResult<String> {
+    loop {
+        while *cursor < data.len() && data[*cursor].is_ascii_whitespace() {
+            *cursor += 1;
+        }
+        if *cursor < data.len() && data[*cursor] == b'#' {
+            while *cursor < data.len() && data[*cursor] != b'\n' {
+                *cursor += 1;
+            }
+            continue;
+        }
+        break;
+    }
+
+    let start = *cursor;
+    while *cursor < data.len() && !data[*cursor].is_ascii_whitespace() {
+        *cursor += 1;
+    }
+    if start == *cursor {
+        return Err("unexpected end of PPM file".into());
+    }
+    return Ok(String::from_utf8_lossy(&data[start..*cursor]).into_owned());
+}
@@ -45,4 +45,34 @@ impl Interval {
     pub fn with_max(self, new_max: float) -> Self {
         Self::new(self.min, new_max)
     }
+
+    #[inline]
+    pub fn with_min(self, new_min: float) -> Self {
+        Self::new(new_min, self.max)
+    }
+
+    #[inline]
+    pub fn size(&self) -> float {
+        self.max - self.min
+    }
+
+    #[inline]
+    pub fn expand(&self, delta: float) -> Self {
+        Self::new(self.min - delta / 2.0, self.max + delta / 2.0)
+    }
+
+    #[inline]
+    pub fn union(&self, other: &Interval) -> Self {
+        Self::new(self.min.min(other.min), self.max.max(other.max))
+    }
+
+    #[inline]
+    pub fn intersection(&self, other: &Interval) -> Self {
+        Self::new(self.min.max(other.min), self.max.min(other.max))
+    }
+
+    #[inline]
+    pub fn lerp(&self, t: float) -> float {
+        self.min + (self.max - self.min) * t
+    }
 }
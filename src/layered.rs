@@ -0,0 +1,156 @@
+use std::rc::Rc;
+
+use crate::{
+    hittable::HitRecord,
+    material::Material,
+    microfacet::{fresnel_dielectric, TrowbridgeReitz},
+    prelude::*,
+    sampler::SampleContext,
+    texture::{SolidColor, Texture},
+};
+
+// Blends two materials, picking `second` with probability given by the
+// scalar value of `weight` at the hit point, e.g. a dirt mask.
+#[derive(Debug)]
+pub struct Mix {
+    first: Rc<dyn Material>,
+    second: Rc<dyn Material>,
+    weight: Rc<dyn Texture>,
+}
+
+impl Mix {
+    pub fn new(first: Rc<dyn Material>, second: Rc<dyn Material>, weight: float) -> Self {
+        Self::from_texture(
+            first,
+            second,
+            Rc::new(SolidColor::new(Color::uniform(weight))),
+        )
+    }
+
+    pub fn from_texture(
+        first: Rc<dyn Material>,
+        second: Rc<dyn Material>,
+        weight: Rc<dyn Texture>,
+    ) -> Self {
+        Self {
+            first,
+            second,
+            weight,
+        }
+    }
+
+    fn weight(&self, hit_record: &HitRecord) -> float {
+        self.weight
+            .scalar_value(hit_record.u, hit_record.v, &hit_record.position)
+            .clamp(0.0, 1.0)
+    }
+}
+
+impl Material for Mix {
+    fn scatter(
+        &self,
+        in_ray: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut SampleContext,
+    ) -> Option<(Color, Ray)> {
+        if sampler.next_1d() < self.weight(hit_record) {
+            self.second.scatter(in_ray, hit_record, sampler)
+        } else {
+            self.first.scatter(in_ray, hit_record, sampler)
+        }
+    }
+
+    fn emitted(&self, in_ray: &Ray, hit_record: &HitRecord) -> Color {
+        let weight = self.weight(hit_record);
+        (1.0 - weight) * self.first.emitted(in_ray, hit_record)
+            + weight * self.second.emitted(in_ray, hit_record)
+    }
+}
+
+// A dielectric clearcoat over any base material, such as varnish or car
+// paint. Light either reflects off the coating with the dielectric Fresnel
+// probability or passes through it to the base, losing the Fresnel
+// reflectance on the way out and being absorbed by the coating's tint.
+#[derive(Debug)]
+pub struct Coated {
+    base: Rc<dyn Material>,
+    index_of_refraction: float,
+    roughness: Rc<dyn Texture>,
+    tint: Color,
+}
+
+impl Coated {
+    pub fn new(base: Rc<dyn Material>, index_of_refraction: float) -> Self {
+        Self {
+            base,
+            index_of_refraction,
+            roughness: Rc::new(SolidColor::new(Color::ZERO)),
+            tint: Color::uniform(1.0),
+        }
+    }
+
+    #[inline]
+    pub fn with_roughness(self, roughness: float) -> Self {
+        self.with_roughness_texture(Rc::new(SolidColor::new(Color::uniform(roughness))))
+    }
+
+    #[inline]
+    pub fn with_roughness_texture(mut self, roughness: Rc<dyn Texture>) -> Self {
+        self.roughness = roughness;
+        self
+    }
+
+    // Color kept by light going down through the coating and back up at
+    // normal incidence. Longer slanted paths are tinted more strongly.
+    #[inline]
+    pub fn with_tint(mut self, tint: Color) -> Self {
+        self.tint = tint;
+        self
+    }
+}
+
+impl Material for Coated {
+    fn scatter(
+        &self,
+        in_ray: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut SampleContext,
+    ) -> Option<(Color, Ray)> {
+        if !hit_record.front_face {
+            return self.base.scatter(in_ray, hit_record, sampler);
+        }
+
+        let frame = Frame::from_normal(&hit_record.normal);
+        let wo = frame.to_local(&-in_ray.direction().unit());
+        if wo.z() <= 0.0 {
+            return None;
+        }
+
+        let entering = fresnel_dielectric(wo.z(), self.index_of_refraction);
+        if sampler.next_1d() < entering {
+            let roughness =
+                self.roughness
+                    .scalar_value(hit_record.u, hit_record.v, &hit_record.position);
+            let distribution = TrowbridgeReitz::from_roughness(roughness);
+            let (wi, _, weight) = distribution.sample_reflection(&wo, sampler)?;
+            return Some((
+                Color::uniform(weight),
+                in_ray.spawn(hit_record.position, frame.to_world(&wi)),
+            ));
+        }
+
+        let (attenuation, scattered) = self.base.scatter(in_ray, hit_record, sampler)?;
+        let cos_theta_i = scattered.direction().unit().dot(&hit_record.normal);
+        if cos_theta_i <= 0.0 {
+            return Some((attenuation, scattered));
+        }
+        let exiting = 1.0 - fresnel_dielectric(cos_theta_i, self.index_of_refraction);
+        let path_lenght = 0.5 * (1.0 / wo.z() + 1.0 / cos_theta_i);
+        let absorption = self.tint.map(|c| c.powf(path_lenght));
+        return Some((exiting * absorption * attenuation, scattered));
+    }
+
+    fn emitted(&self, in_ray: &Ray, hit_record: &HitRecord) -> Color {
+        self.base.emitted(in_ray, hit_record)
+    }
+}
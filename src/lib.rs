@@ -0,0 +1,44 @@
+#![allow(clippy::needless_return)]
+
+pub mod aabb;
+pub mod camera;
+pub mod checkpoint;
+pub mod conductor;
+pub mod csg;
+pub mod cuboid;
+pub mod curve;
+pub mod cylinder;
+pub mod distributed;
+pub mod film;
+pub mod filter;
+pub mod frame;
+pub mod hair;
+pub mod heightfield;
+pub mod hittable;
+pub mod hittable_list;
+pub mod image;
+pub mod interval;
+pub mod layered;
+pub mod material;
+pub mod matrix;
+pub mod medium;
+pub mod microfacet;
+pub mod noise;
+pub mod normal_map;
+pub mod prelude;
+pub mod principled;
+pub mod quat;
+pub mod random;
+pub mod ray;
+pub mod rough_dielectric;
+pub mod sampler;
+pub mod scene;
+pub mod sdf;
+pub mod spectrum;
+pub mod sphere;
+pub mod subsurface;
+pub mod texture;
+pub mod thin_film;
+pub mod transform;
+pub mod vec3;
+pub mod volume;
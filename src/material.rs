@@ -1,56 +1,97 @@
-use std::fmt::Debug;
+use std::{fmt::Debug, rc::Rc};
 
 use crate::hittable::HitRecord;
 use crate::prelude::*;
+use crate::sampler::SampleContext;
+use crate::spectrum::Ior;
+use crate::texture::{SolidColor, Texture};
+use crate::thin_film::{Complex, ThinFilm};
 
+// Materials draw the random numbers of their choices from `sampler`.
 pub trait Material: Debug {
-    fn scatter(&self, in_ray: &Ray, hit_record: &HitRecord) -> Option<(Color, Ray)>;
+    fn scatter(
+        &self,
+        in_ray: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut SampleContext,
+    ) -> Option<(Color, Ray)>;
+
+    fn emitted(&self, _in_ray: &Ray, _hit_record: &HitRecord) -> Color {
+        Color::ZERO
+    }
 }
 
 #[derive(Debug)]
 pub struct Lambertian {
-    albedo: Color,
+    albedo: Rc<dyn Texture>,
 }
 
 impl Lambertian {
     pub fn new(albedo: Color) -> Self {
+        Self::from_texture(Rc::new(SolidColor::new(albedo)))
+    }
+
+    pub fn from_texture(albedo: Rc<dyn Texture>) -> Self {
         Self { albedo }
     }
 }
 
 impl Material for Lambertian {
-    fn scatter(&self, _in_ray: &Ray, hit_record: &HitRecord) -> Option<(Color, Ray)> {
-        let mut scatter_direction = hit_record.normal + Vec3::random_unit_vector();
+    fn scatter(
+        &self,
+        in_ray: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut SampleContext,
+    ) -> Option<(Color, Ray)> {
+        let mut scatter_direction = hit_record.normal + Vec3::random_unit_vector(sampler);
         if scatter_direction.near_zero() {
             scatter_direction = hit_record.normal;
         }
         return Some((
-            self.albedo,
-            Ray::new(hit_record.position, scatter_direction),
+            self.albedo
+                .value(hit_record.u, hit_record.v, &hit_record.position),
+            in_ray.spawn(hit_record.position, scatter_direction),
         ));
     }
 }
 
 #[derive(Debug)]
 pub struct Metal {
-    albedo: Color,
-    fuzz: float,
+    albedo: Rc<dyn Texture>,
+    fuzz: Rc<dyn Texture>,
 }
 
 impl Metal {
     pub fn new(albedo: Color, fuzz: float) -> Self {
+        Self::from_texture(Rc::new(SolidColor::new(albedo)), fuzz)
+    }
+
+    pub fn from_texture(albedo: Rc<dyn Texture>, fuzz: float) -> Self {
+        Self::from_textures(albedo, Rc::new(SolidColor::new(Color::uniform(fuzz))))
+    }
+
+    pub fn from_textures(albedo: Rc<dyn Texture>, fuzz: Rc<dyn Texture>) -> Self {
         Self { albedo, fuzz }
     }
 }
 
 impl Material for Metal {
-    fn scatter(&self, in_ray: &Ray, hit_record: &HitRecord) -> Option<(Color, Ray)> {
+    fn scatter(
+        &self,
+        in_ray: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut SampleContext,
+    ) -> Option<(Color, Ray)> {
         let reflected = in_ray.direction().unit().reflect(&hit_record.normal);
+        let fuzz = self
+            .fuzz
+            .scalar_value(hit_record.u, hit_record.v, &hit_record.position);
         return Some((
-            self.albedo,
-            Ray::new(
+            self.albedo
+                .value(hit_record.u, hit_record.v, &hit_record.position),
+            in_ray.spawn(
                 hit_record.position,
-                reflected + self.fuzz * Vec3::random_unit_vector(),
+                reflected + fuzz * Vec3::random_unit_vector(sampler),
             ),
         ));
     }
@@ -58,23 +99,44 @@ impl Material for Metal {
 
 #[derive(Debug)]
 pub struct Dielectric {
-    index_of_refraction: float,
+    index_of_refraction: Ior,
+    thin_film: Option<ThinFilm>,
 }
 
 impl Dielectric {
     pub fn new(index_of_refraction: float) -> Self {
+        Self::from_ior(Ior::Constant(index_of_refraction))
+    }
+
+    // A dielectric whose index varies with the ray's wavelength, which
+    // disperses light in spectral mode.
+    pub fn from_ior(index_of_refraction: Ior) -> Self {
         Self {
             index_of_refraction,
+            thin_film: None,
         }
     }
+
+    // Coats the outside of the surface, e.g. for soap bubbles.
+    #[inline]
+    pub fn with_thin_film(mut self, thin_film: ThinFilm) -> Self {
+        self.thin_film = Some(thin_film);
+        self
+    }
 }
 
 impl Material for Dielectric {
-    fn scatter(&self, in_ray: &Ray, hit_record: &HitRecord) -> Option<(Color, Ray)> {
+    fn scatter(
+        &self,
+        in_ray: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut SampleContext,
+    ) -> Option<(Color, Ray)> {
+        let index_of_refraction = self.index_of_refraction.at(in_ray.wavelength());
         let refraction_ratio = if hit_record.front_face {
-            1.0 / self.index_of_refraction
+            1.0 / index_of_refraction
         } else {
-            self.index_of_refraction
+            index_of_refraction
         };
 
         let unit_direction = in_ray.direction().unit();
@@ -82,16 +144,50 @@ impl Material for Dielectric {
         let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
 
         let cannot_refract = refraction_ratio * sin_theta > 1.0;
-        let scatter_direction =
-            if cannot_refract || reflectance(cos_theta, refraction_ratio) > rand_norm() {
-                unit_direction.reflect(&hit_record.normal)
-            } else {
-                unit_direction.refract(&hit_record.normal, refraction_ratio)
-            };
+        let Some(thin_film) = &self.thin_film else {
+            let scatter_direction =
+                if cannot_refract || reflectance(cos_theta, refraction_ratio) > sampler.next_1d() {
+                    unit_direction.reflect(&hit_record.normal)
+                } else {
+                    unit_direction.refract(&hit_record.normal, refraction_ratio)
+                };
+            return Some((
+                Color::uniform(1.0),
+                in_ray.spawn(hit_record.position, scatter_direction),
+            ));
+        };
 
+        // The film reflects colors differently, so pick the lobe by the
+        // average reflectance and weight the chosen one per channel.
+        let fresnel = if cannot_refract {
+            Color::uniform(1.0)
+        } else {
+            thin_film.reflectance(hit_record, cos_theta, in_ray.wavelength(), |lambda| {
+                let inside = Complex::real(self.index_of_refraction.at(Some(lambda)));
+                let outside = Complex::real(1.0);
+                if hit_record.front_face {
+                    (outside, inside)
+                } else {
+                    (inside, outside)
+                }
+            })
+        };
+        let probability = ((fresnel.r() + fresnel.g() + fresnel.b()) / 3.0).clamp(1e-3, 1.0);
+        if cannot_refract || probability > sampler.next_1d() {
+            return Some((
+                fresnel / probability,
+                in_ray.spawn(
+                    hit_record.position,
+                    unit_direction.reflect(&hit_record.normal),
+                ),
+            ));
+        }
         return Some((
-            Color::uniform(1.0),
-            Ray::new(hit_record.position, scatter_direction),
+            (Color::uniform(1.0) - fresnel) / (1.0 - probability),
+            in_ray.spawn(
+                hit_record.position,
+                unit_direction.refract(&hit_record.normal, refraction_ratio),
+            ),
         ));
     }
 }
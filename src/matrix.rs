@@ -0,0 +1,466 @@
+use std::{
+    fmt::Debug,
+    ops::{Add, AddAssign, Div, Mul, MulAssign, Neg, Sub, SubAssign},
+};
+
+use crate::prelude::*;
+
+#[derive(Clone, Copy, Default)]
+pub struct Mat3 {
+    pub m: [[float; 3]; 3],
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct Mat4 {
+    pub m: [[float; 4]; 4],
+}
+
+impl Mat3 {
+    pub const ZERO: Mat3 = Mat3::from_rows([[0.0; 3]; 3]);
+    pub const IDENTITY: Mat3 = Mat3::from_diagonal(Vec3::uniform(1.0));
+
+    #[inline]
+    pub const fn from_rows(m: [[float; 3]; 3]) -> Self {
+        Self { m }
+    }
+
+    #[inline]
+    pub const fn from_row_vectors(r0: Vec3, r1: Vec3, r2: Vec3) -> Self {
+        Self::from_rows([r0.e, r1.e, r2.e])
+    }
+
+    #[inline]
+    pub const fn from_cols(c0: Vec3, c1: Vec3, c2: Vec3) -> Self {
+        Self::from_rows([
+            [c0.e[0], c1.e[0], c2.e[0]],
+            [c0.e[1], c1.e[1], c2.e[1]],
+            [c0.e[2], c1.e[2], c2.e[2]],
+        ])
+    }
+
+    #[inline]
+    pub const fn from_diagonal(d: Vec3) -> Self {
+        Self::from_rows([[d.e[0], 0.0, 0.0], [0.0, d.e[1], 0.0], [0.0, 0.0, d.e[2]]])
+    }
+
+    #[inline]
+    pub fn scale(factors: Vec3) -> Self {
+        Self::from_diagonal(factors)
+    }
+
+    pub fn rotation(axis: &Vec3, angle: float) -> Self {
+        let a = axis.unit();
+        let (sin, cos) = angle.sin_cos();
+        let t = 1.0 - cos;
+        Self::from_rows([
+            [
+                t * a.x() * a.x() + cos,
+                t * a.x() * a.y() - sin * a.z(),
+                t * a.x() * a.z() + sin * a.y(),
+            ],
+            [
+                t * a.x() * a.y() + sin * a.z(),
+                t * a.y() * a.y() + cos,
+                t * a.y() * a.z() - sin * a.x(),
+            ],
+            [
+                t * a.x() * a.z() - sin * a.y(),
+                t * a.y() * a.z() + sin * a.x(),
+                t * a.z() * a.z() + cos,
+            ],
+        ])
+    }
+
+    #[inline]
+    pub fn row(&self, i: usize) -> Vec3 {
+        Vec3::from_array(self.m[i])
+    }
+
+    #[inline]
+    pub fn col(&self, j: usize) -> Vec3 {
+        Vec3::new(self.m[0][j], self.m[1][j], self.m[2][j])
+    }
+
+    #[inline]
+    pub fn transpose(&self) -> Self {
+        Self::from_cols(self.row(0), self.row(1), self.row(2))
+    }
+
+    #[inline]
+    pub fn determinant(&self) -> float {
+        self.row(0).dot(&self.row(1).cross(&self.row(2)))
+    }
+
+    pub fn inverse(&self) -> Option<Self> {
+        let (r0, r1, r2) = (self.row(0), self.row(1), self.row(2));
+        let c0 = r1.cross(&r2);
+        let det = r0.dot(&c0);
+        // Only exactly singular matrices are rejected, since tiny but valid
+        // scales have tiny determinants too.
+        if det == 0.0 {
+            return None;
+        }
+        let adjugate = Self::from_cols(c0, r2.cross(&r0), r0.cross(&r1));
+        return Some(adjugate / det);
+    }
+
+    #[inline]
+    pub fn transform(&self, v: &Vec3) -> Vec3 {
+        Vec3::new(self.row(0).dot(v), self.row(1).dot(v), self.row(2).dot(v))
+    }
+}
+
+impl Mat4 {
+    pub const ZERO: Mat4 = Mat4::from_rows([[0.0; 4]; 4]);
+    pub const IDENTITY: Mat4 = Mat4::from_rows([
+        [1.0, 0.0, 0.0, 0.0],
+        [0.0, 1.0, 0.0, 0.0],
+        [0.0, 0.0, 1.0, 0.0],
+        [0.0, 0.0, 0.0, 1.0],
+    ]);
+
+    #[inline]
+    pub const fn from_rows(m: [[float; 4]; 4]) -> Self {
+        Self { m }
+    }
+
+    #[inline]
+    pub const fn from_linear_translation(linear: Mat3, translation: Vec3) -> Self {
+        let l = linear.m;
+        let t = translation.e;
+        Self::from_rows([
+            [l[0][0], l[0][1], l[0][2], t[0]],
+            [l[1][0], l[1][1], l[1][2], t[1]],
+            [l[2][0], l[2][1], l[2][2], t[2]],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    #[inline]
+    pub fn translation(offset: Vec3) -> Self {
+        Self::from_linear_translation(Mat3::IDENTITY, offset)
+    }
+
+    #[inline]
+    pub fn scale(factors: Vec3) -> Self {
+        Self::from_linear_translation(Mat3::scale(factors), Vec3::ZERO)
+    }
+
+    #[inline]
+    pub fn rotation(axis: &Vec3, angle: float) -> Self {
+        Self::from_linear_translation(Mat3::rotation(axis, angle), Vec3::ZERO)
+    }
+
+    #[inline]
+    pub fn linear(&self) -> Mat3 {
+        let m = self.m;
+        Mat3::from_rows([
+            [m[0][0], m[0][1], m[0][2]],
+            [m[1][0], m[1][1], m[1][2]],
+            [m[2][0], m[2][1], m[2][2]],
+        ])
+    }
+
+    #[inline]
+    pub fn translation_part(&self) -> Vec3 {
+        Vec3::new(self.m[0][3], self.m[1][3], self.m[2][3])
+    }
+
+    pub fn transpose(&self) -> Self {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = self.m[j][i];
+            }
+        }
+        Self::from_rows(m)
+    }
+
+    pub fn determinant(&self) -> float {
+        let [s, c] = self.minors();
+        return s[0] * c[5] - s[1] * c[4] + s[2] * c[3] + s[3] * c[2] - s[4] * c[1] + s[5] * c[0];
+    }
+
+    pub fn inverse(&self) -> Option<Self> {
+        let m = self.m;
+        let [s, c] = self.minors();
+        let det = self.determinant();
+        // Only exactly singular matrices are rejected, since tiny but valid
+        // scales have tiny determinants too.
+        if det == 0.0 {
+            return None;
+        }
+
+        let adjugate = Self::from_rows([
+            [
+                m[1][1] * c[5] - m[1][2] * c[4] + m[1][3] * c[3],
+                -m[0][1] * c[5] + m[0][2] * c[4] - m[0][3] * c[3],
+                m[3][1] * s[5] - m[3][2] * s[4] + m[3][3] * s[3],
+                -m[2][1] * s[5] + m[2][2] * s[4] - m[2][3] * s[3],
+            ],
+            [
+                -m[1][0] * c[5] + m[1][2] * c[2] - m[1][3] * c[1],
+                m[0][0] * c[5] - m[0][2] * c[2] + m[0][3] * c[1],
+                -m[3][0] * s[5] + m[3][2] * s[2] - m[3][3] * s[1],
+                m[2][0] * s[5] - m[2][2] * s[2] + m[2][3] * s[1],
+            ],
+            [
+                m[1][0] * c[4] - m[1][1] * c[2] + m[1][3] * c[0],
+                -m[0][0] * c[4] + m[0][1] * c[2] - m[0][3] * c[0],
+                m[3][0] * s[4] - m[3][1] * s[2] + m[3][3] * s[0],
+                -m[2][0] * s[4] + m[2][1] * s[2] - m[2][3] * s[0],
+            ],
+            [
+                -m[1][0] * c[3] + m[1][1] * c[1] - m[1][2] * c[0],
+                m[0][0] * c[3] - m[0][1] * c[1] + m[0][2] * c[0],
+                -m[3][0] * s[3] + m[3][1] * s[1] - m[3][2] * s[0],
+                m[2][0] * s[3] - m[2][1] * s[1] + m[2][2] * s[0],
+            ],
+        ]);
+        return Some(adjugate / det);
+    }
+
+    // 2x2 minors of the top two rows (s) and of the bottom two rows (c), as
+    // used by the Laplace expansion of the determinant.
+    fn minors(&self) -> [[float; 6]; 2] {
+        let m = self.m;
+        let s = [
+            m[0][0] * m[1][1] - m[1][0] * m[0][1],
+            m[0][0] * m[1][2] - m[1][0] * m[0][2],
+            m[0][0] * m[1][3] - m[1][0] * m[0][3],
+            m[0][1] * m[1][2] - m[1][1] * m[0][2],
+            m[0][1] * m[1][3] - m[1][1] * m[0][3],
+            m[0][2] * m[1][3] - m[1][2] * m[0][3],
+        ];
+        let c = [
+            m[2][0] * m[3][1] - m[3][0] * m[2][1],
+            m[2][0] * m[3][2] - m[3][0] * m[2][2],
+            m[2][0] * m[3][3] - m[3][0] * m[2][3],
+            m[2][1] * m[3][2] - m[3][1] * m[2][2],
+            m[2][1] * m[3][3] - m[3][1] * m[2][3],
+            m[2][2] * m[3][3] - m[3][2] * m[2][3],
+        ];
+        [s, c]
+    }
+
+    #[inline]
+    pub fn transform_point(&self, point: &Point3) -> Point3 {
+        let m = self.m;
+        let p = self.linear().transform(point) + self.translation_part();
+        let w = m[3][0] * point.x() + m[3][1] * point.y() + m[3][2] * point.z() + m[3][3];
+        if w == 1.0 {
+            p
+        } else {
+            p / w
+        }
+    }
+
+    #[inline]
+    pub fn transform_vector(&self, vector: &Vec3) -> Vec3 {
+        self.linear().transform(vector)
+    }
+
+    // Normals transform by the inverse transpose so they stay perpendicular to
+    // surfaces under non-uniform scaling. None if the matrix is singular.
+    #[inline]
+    pub fn transform_normal(&self, normal: &Vec3) -> Option<Vec3> {
+        self.linear()
+            .inverse()
+            .map(|inverse| inverse.transpose().transform(normal))
+    }
+}
+
+macro_rules! impl_matrix_debug {
+    ($type:ident) => {
+        impl Debug for $type {
+            #[inline]
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                Debug::fmt(&self.m, f)
+            }
+        }
+    };
+}
+
+impl_matrix_debug!(Mat3);
+impl_matrix_debug!(Mat4);
+
+macro_rules! impl_borrowed_matrix_bop {
+    ($lhs:ty, $rhs:ty, $output:ty, $trait:ident, $func:ident) => {
+        impl $trait<&$rhs> for &$lhs {
+            type Output = $output;
+
+            #[inline]
+            fn $func(self, rhs: &$rhs) -> Self::Output {
+                $trait::$func(*self, *rhs)
+            }
+        }
+
+        impl $trait<&$rhs> for $lhs {
+            type Output = $output;
+
+            #[inline]
+            fn $func(self, rhs: &$rhs) -> Self::Output {
+                $trait::$func(self, *rhs)
+            }
+        }
+
+        impl $trait<$rhs> for &$lhs {
+            type Output = $output;
+
+            #[inline]
+            fn $func(self, rhs: $rhs) -> Self::Output {
+                $trait::$func(*self, rhs)
+            }
+        }
+    };
+}
+
+macro_rules! impl_matrix_element_wise_op {
+    ($type:ident, $trait:ident, $func:ident) => {
+        impl $trait for $type {
+            type Output = Self;
+
+            #[inline]
+            fn $func(self, rhs: Self) -> Self::Output {
+                let mut m = self.m;
+                for (row, rhs_row) in m.iter_mut().zip(rhs.m) {
+                    for (value, rhs_value) in row.iter_mut().zip(rhs_row) {
+                        *value = $trait::$func(*value, rhs_value);
+                    }
+                }
+                Self::from_rows(m)
+            }
+        }
+        impl_borrowed_matrix_bop!($type, $type, $type, $trait, $func);
+    };
+}
+
+macro_rules! impl_matrix_scalar_op {
+    ($type:ident, $trait:ident, $func:ident) => {
+        impl $trait<float> for $type {
+            type Output = Self;
+
+            #[inline]
+            fn $func(self, rhs: float) -> Self::Output {
+                Self::from_rows(self.m.map(|row| row.map(|value| $trait::$func(value, rhs))))
+            }
+        }
+        impl_borrowed_matrix_bop!($type, float, $type, $trait, $func);
+    };
+}
+
+macro_rules! impl_matrix_product {
+    ($type:ident, $n:literal) => {
+        impl Mul for $type {
+            type Output = Self;
+
+            #[inline]
+            fn mul(self, rhs: Self) -> Self::Output {
+                let mut m = [[0.0; $n]; $n];
+                for (i, row) in m.iter_mut().enumerate() {
+                    for (j, value) in row.iter_mut().enumerate() {
+                        *value = (0..$n).map(|k| self.m[i][k] * rhs.m[k][j]).sum();
+                    }
+                }
+                Self::from_rows(m)
+            }
+        }
+        impl_borrowed_matrix_bop!($type, $type, $type, Mul, mul);
+
+        impl Mul<$type> for float {
+            type Output = $type;
+
+            #[inline]
+            fn mul(self, rhs: $type) -> Self::Output {
+                rhs * self
+            }
+        }
+        impl_borrowed_matrix_bop!(float, $type, $type, Mul, mul);
+
+        impl Neg for $type {
+            type Output = Self;
+
+            #[inline]
+            fn neg(self) -> Self::Output {
+                self * -1.0
+            }
+        }
+    };
+}
+
+macro_rules! impl_matrix_op_assign {
+    ($type:ident, $trait:ident, $func:ident, $op:ident, $rhs:ty) => {
+        impl $trait<$rhs> for $type {
+            #[inline]
+            fn $func(&mut self, rhs: $rhs) {
+                *self = self.$op(rhs);
+            }
+        }
+    };
+}
+
+macro_rules! impl_matrix_ops {
+    ($type:ident, $n:literal) => {
+        impl_matrix_element_wise_op!($type, Add, add);
+        impl_matrix_element_wise_op!($type, Sub, sub);
+        impl_matrix_scalar_op!($type, Mul, mul);
+        impl_matrix_scalar_op!($type, Div, div);
+        impl_matrix_product!($type, $n);
+
+        impl_matrix_op_assign!($type, AddAssign, add_assign, add, $type);
+        impl_matrix_op_assign!($type, SubAssign, sub_assign, sub, $type);
+        impl_matrix_op_assign!($type, MulAssign, mul_assign, mul, $type);
+        impl_matrix_op_assign!($type, MulAssign, mul_assign, mul, float);
+    };
+}
+
+impl_matrix_ops!(Mat3, 3);
+impl_matrix_ops!(Mat4, 4);
+
+impl Mul<Vec3> for Mat3 {
+    type Output = Vec3;
+
+    #[inline]
+    fn mul(self, rhs: Vec3) -> Self::Output {
+        self.transform(&rhs)
+    }
+}
+impl_borrowed_matrix_bop!(Mat3, Vec3, Vec3, Mul, mul);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(a: &Mat4, b: &Mat4) {
+        for (row_a, row_b) in a.m.iter().zip(&b.m) {
+            for (x, y) in row_a.iter().zip(row_b) {
+                assert!((x - y).abs() < 1e-5, "{:?} != {:?}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn inverse_undoes_the_matrix() {
+        let affine = Mat4::translation(Vec3::new(1.0, -2.0, 3.0))
+            * Mat4::rotation(&Vec3::new(1.0, 1.0, 0.0), 0.7)
+            * Mat4::scale(Vec3::new(2.0, 0.5, 3.0));
+        let projective = Mat4::from_rows([
+            [2.0, 0.0, 1.0, 0.0],
+            [0.0, 1.0, 0.0, 3.0],
+            [1.0, 0.0, 1.0, 0.0],
+            [0.0, 0.5, 0.0, 1.0],
+        ]);
+        for m in [affine, projective] {
+            let inverse = m.inverse().unwrap();
+            assert_near(&(m * inverse), &Mat4::IDENTITY);
+            assert_near(&(inverse * m), &Mat4::IDENTITY);
+        }
+    }
+
+    #[test]
+    fn singular_matrices_have_no_inverse() {
+        let flat = Mat4::scale(Vec3::new(1.0, 0.0, 1.0));
+        assert!(flat.inverse().is_none());
+        assert!(flat.transform_normal(&Vec3::Y).is_none());
+    }
+}
@@ -0,0 +1,216 @@
+use std::rc::Rc;
+
+use crate::{
+    aabb::Aabb,
+    hittable::{HitRecord, Hittable},
+    interval::Interval,
+    material::Material,
+    prelude::*,
+    sampler::SampleContext,
+    texture::{SolidColor, Texture},
+};
+
+// Samples a new propagation direction for light travelling along `direction`
+// from the Henyey-Greenstein phase function. Positive `g` scatters forward,
+// negative `g` backward, and 0 is isotropic.
+pub fn sample_henyey_greenstein(direction: &Vec3, g: float, sampler: &mut SampleContext) -> Vec3 {
+    let [u, v] = sampler.next_2d();
+    let cos_theta = if g.abs() < 1e-3 {
+        1.0 - 2.0 * u
+    } else {
+        let s = (1.0 - g * g) / (1.0 - g + 2.0 * g * u);
+        ((1.0 + g * g - s * s) / (2.0 * g)).clamp(-1.0, 1.0)
+    };
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    let phi = 2.0 * PI * v;
+    let local = Vec3::new(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta);
+    return Frame::from_normal(&direction.unit()).to_world(&local);
+}
+
+// Phase functions are sampled exactly, so scattering is only weighted by the
+// single scattering albedo.
+#[derive(Debug)]
+pub struct Isotropic {
+    albedo: Rc<dyn Texture>,
+}
+
+impl Isotropic {
+    pub fn new(albedo: Color) -> Self {
+        Self::from_texture(Rc::new(SolidColor::new(albedo)))
+    }
+
+    pub fn from_texture(albedo: Rc<dyn Texture>) -> Self {
+        Self { albedo }
+    }
+}
+
+impl Material for Isotropic {
+    fn scatter(
+        &self,
+        in_ray: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut SampleContext,
+    ) -> Option<(Color, Ray)> {
+        let albedo = self
+            .albedo
+            .value(hit_record.u, hit_record.v, &hit_record.position);
+        return Some((
+            albedo,
+            in_ray.spawn(hit_record.position, Vec3::random_unit_vector(sampler)),
+        ));
+    }
+}
+
+#[derive(Debug)]
+pub struct HenyeyGreenstein {
+    albedo: Rc<dyn Texture>,
+    g: float,
+}
+
+impl HenyeyGreenstein {
+    pub fn new(albedo: Color, g: float) -> Self {
+        Self::from_texture(Rc::new(SolidColor::new(albedo)), g)
+    }
+
+    pub fn from_texture(albedo: Rc<dyn Texture>, g: float) -> Self {
+        Self {
+            albedo,
+            g: g.clamp(-0.99, 0.99),
+        }
+    }
+}
+
+impl Material for HenyeyGreenstein {
+    fn scatter(
+        &self,
+        in_ray: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut SampleContext,
+    ) -> Option<(Color, Ray)> {
+        let albedo = self
+            .albedo
+            .value(hit_record.u, hit_record.v, &hit_record.position);
+        let direction = sample_henyey_greenstein(&in_ray.direction(), self.g, sampler);
+        return Some((albedo, in_ray.spawn(hit_record.position, direction)));
+    }
+}
+
+// A homogeneous volume filling a closed, convex boundary. Rays passing through
+// it collide after an exponentially distributed distance, at which point the
+// phase function takes over as the material. The boundary itself is not a
+// surface, so rays that don't collide pass through it unchanged.
+pub struct ConstantMedium {
+    boundary: Box<dyn Hittable>,
+    density: float,
+    phase_function: Rc<dyn Material>,
+}
+
+impl ConstantMedium {
+    pub fn new(
+        boundary: Box<dyn Hittable>,
+        density: float,
+        phase_function: Rc<dyn Material>,
+    ) -> Self {
+        Self {
+            boundary,
+            density,
+            phase_function,
+        }
+    }
+
+    pub fn isotropic(boundary: Box<dyn Hittable>, density: float, albedo: Color) -> Self {
+        Self::new(boundary, density, Rc::new(Isotropic::new(albedo)))
+    }
+}
+
+impl Hittable for ConstantMedium {
+    fn hit(&self, _ray: &Ray, _t_interval: Interval) -> Option<HitRecord> {
+        None
+    }
+
+    fn sample_medium(
+        &self,
+        ray: &Ray,
+        t_interval: Interval,
+        sampler: &mut SampleContext,
+    ) -> Option<HitRecord> {
+        // Find the span inside the boundary, even if the ray starts inside.
+        let entry = self.boundary.hit(ray, Interval::UNIVERSE)?;
+        let exit = self
+            .boundary
+            .hit(ray, Interval::new(entry.t + 1e-4, float::INFINITY))?;
+        let span = Interval::new(
+            entry.t.max(t_interval.min).max(0.0),
+            exit.t.min(t_interval.max),
+        );
+        if span.min >= span.max {
+            return None;
+        }
+
+        let speed = ray.direction().lenght();
+        let distance = -(1.0 - sampler.next_1d()).ln() / self.density;
+        if distance > span.size() * speed {
+            return None;
+        }
+
+        let t = span.min + distance / speed;
+        return Some(HitRecord::from_outward_normal(
+            t,
+            ray.at(t),
+            self.phase_function.clone(),
+            -ray.direction().unit(),
+            ray,
+        ));
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.boundary.bounding_box()
+    }
+}
+
+// Homogeneous fog filling the bounds of the whole scene.
+#[derive(Clone, Copy, Debug)]
+pub struct Fog {
+    density: float,
+    albedo: Color,
+    g: float,
+}
+
+impl Fog {
+    pub fn new(density: float, albedo: Color) -> Self {
+        Self {
+            density,
+            albedo,
+            g: 0.0,
+        }
+    }
+
+    // Henyey-Greenstein asymmetry; real fog scatters mostly forward.
+    #[inline]
+    pub fn with_anisotropy(mut self, g: float) -> Self {
+        self.g = g.clamp(-0.99, 0.99);
+        self
+    }
+
+    // Samples a free flight distance along `ray` through the fog inside
+    // `bounds`, up to the next surface at `t_max`. Returns the scattered ray
+    // if the ray interacts with the fog before reaching it.
+    pub fn scatter(
+        &self,
+        ray: &Ray,
+        bounds: &Aabb,
+        t_max: float,
+        sampler: &mut SampleContext,
+    ) -> Option<(Color, Ray)> {
+        let span = bounds.hit(ray, Interval::new(0.0, t_max))?;
+        let speed = ray.direction().lenght();
+        let distance = -(1.0 - sampler.next_1d()).ln() / self.density;
+        if distance > span.size() * speed {
+            return None;
+        }
+
+        let position = ray.at(span.min + distance / speed);
+        let direction = sample_henyey_greenstein(&ray.direction(), self.g, sampler);
+        return Some((self.albedo, ray.spawn(position, direction)));
+    }
+}
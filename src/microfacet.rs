@@ -0,0 +1,211 @@
+use crate::{prelude::*, sampler::SampleContext};
+
+// The Trowbridge-Reitz (GGX) microfacet distribution with Smith masking and
+// shadowing. Directions are in a local shading frame whose z axis is the
+// macrosurface normal.
+#[derive(Clone, Copy, Debug)]
+pub struct TrowbridgeReitz {
+    alpha: float,
+}
+
+impl TrowbridgeReitz {
+    // Below this the surface is treated as a perfect mirror, as sampling the
+    // distribution becomes numerically unstable.
+    const SMOOTH_ALPHA: float = 1e-3;
+
+    #[inline]
+    pub fn new(alpha: float) -> Self {
+        Self { alpha }
+    }
+
+    // Perceptually linear roughness in [0, 1] as used by most authoring tools.
+    #[inline]
+    pub fn from_roughness(roughness: float) -> Self {
+        Self::new(roughness.clamp(0.0, 1.0).powi(2))
+    }
+
+    #[inline]
+    pub fn alpha(&self) -> float {
+        self.alpha
+    }
+
+    #[inline]
+    pub fn is_smooth(&self) -> bool {
+        self.alpha < Self::SMOOTH_ALPHA
+    }
+
+    pub fn d(&self, wm: &Vec3) -> float {
+        let cos2_theta = wm.z() * wm.z();
+        if cos2_theta <= 0.0 {
+            return 0.0;
+        }
+        let tan2_theta = (1.0 - cos2_theta) / cos2_theta;
+        let alpha2 = self.alpha * self.alpha;
+        let e = 1.0 + tan2_theta / alpha2;
+        return 1.0 / (PI * alpha2 * cos2_theta * cos2_theta * e * e);
+    }
+
+    pub fn lambda(&self, w: &Vec3) -> float {
+        let cos2_theta = w.z() * w.z();
+        if cos2_theta <= 0.0 {
+            return 0.0;
+        }
+        let tan2_theta = (1.0 - cos2_theta) / cos2_theta;
+        return ((1.0 + self.alpha * self.alpha * tan2_theta).sqrt() - 1.0) / 2.0;
+    }
+
+    #[inline]
+    pub fn g1(&self, w: &Vec3) -> float {
+        1.0 / (1.0 + self.lambda(w))
+    }
+
+    #[inline]
+    pub fn g(&self, wo: &Vec3, wi: &Vec3) -> float {
+        1.0 / (1.0 + self.lambda(wo) + self.lambda(wi))
+    }
+
+    // Distribution of normals visible from `w`, D_w(wm) = G1(w) max(0, w.wm)
+    // D(wm) / |cos(theta_w)|.
+    #[inline]
+    pub fn visible_d(&self, w: &Vec3, wm: &Vec3) -> float {
+        self.g1(w) * w.dot(wm).max(0.0) * self.d(wm) / w.z().abs()
+    }
+
+    // Samples a microfacet normal visible from `w` (Heitz, "Sampling the GGX
+    // Distribution of Visible Normals", 2018). `w` must be in the upper
+    // hemisphere.
+    pub fn sample_visible_normal(&self, w: &Vec3, sampler: &mut SampleContext) -> Vec3 {
+        let wh = Vec3::new(self.alpha * w.x(), self.alpha * w.y(), w.z()).unit();
+        let lenght_squared = wh.x() * wh.x() + wh.y() * wh.y();
+        let t1 = if lenght_squared > 0.0 {
+            Vec3::new(-wh.y(), wh.x(), 0.0) / lenght_squared.sqrt()
+        } else {
+            Vec3::X
+        };
+        let t2 = wh.cross(&t1);
+
+        let [u, v] = sampler.next_2d();
+        let (r, phi) = (u.sqrt(), 2.0 * PI * v);
+        let p1 = r * phi.cos();
+        let s = 0.5 * (1.0 + wh.z());
+        let p2 = (1.0 - s) * (1.0 - p1 * p1).max(0.0).sqrt() + s * r * phi.sin();
+        let p3 = (1.0 - p1 * p1 - p2 * p2).max(0.0).sqrt();
+
+        let nh = p1 * t1 + p2 * t2 + p3 * wh;
+        return Vec3::new(self.alpha * nh.x(), self.alpha * nh.y(), nh.z().max(1e-6)).unit();
+    }
+
+    // Samples a reflected direction, returning it with the microfacet normal
+    // it was reflected about and its weight f cos / pdf excluding Fresnel,
+    // which reduces to G2 / G1 with visible normal sampling.
+    pub fn sample_reflection(
+        &self,
+        wo: &Vec3,
+        sampler: &mut SampleContext,
+    ) -> Option<(Vec3, Vec3, float)> {
+        if self.is_smooth() {
+            return Some((Vec3::new(-wo.x(), -wo.y(), wo.z()), Vec3::Z, 1.0));
+        }
+        let wm = self.sample_visible_normal(wo, sampler);
+        let wi = (-wo).reflect(&wm);
+        if wi.z() <= 0.0 {
+            return None;
+        }
+        return Some((wi, wm, self.g(wo, &wi) / self.g1(wo)));
+    }
+
+    // Samples reflection or transmission through a dielectric interface with
+    // relative index `eta`, chosen in proportion to the exact Fresnel term so
+    // that the weight again reduces to G2 / G1.
+    pub fn sample_dielectric(
+        &self,
+        wo: &Vec3,
+        eta: float,
+        sampler: &mut SampleContext,
+    ) -> Option<(Vec3, float)> {
+        let wm = if self.is_smooth() {
+            Vec3::Z
+        } else {
+            self.sample_visible_normal(wo, sampler)
+        };
+        let reflectance = fresnel_dielectric(wo.dot(&wm), eta);
+        let wi = if sampler.next_1d() < reflectance {
+            let wi = (-wo).reflect(&wm);
+            (wi.z() > 0.0).then_some(wi)?
+        } else {
+            let wi = refract(wo, &wm, eta)?;
+            (wi.z() < 0.0).then_some(wi)?
+        };
+        let weight = if self.is_smooth() {
+            1.0
+        } else {
+            self.g(wo, &wi) / self.g1(wo)
+        };
+        return Some((wi, weight));
+    }
+}
+
+// Fresnel reflectance of an interface between dielectrics for light arriving
+// at `cos_theta_i` from the side with index 1, where `eta` is the relative
+// index of the other side. Negative cosines come from the other side.
+pub fn fresnel_dielectric(cos_theta_i: float, eta: float) -> float {
+    let (cos_theta_i, eta) = if cos_theta_i < 0.0 {
+        (-cos_theta_i, 1.0 / eta)
+    } else {
+        (cos_theta_i, eta)
+    };
+    let cos_theta_i = cos_theta_i.min(1.0);
+    let sin2_theta_t = (1.0 - cos_theta_i * cos_theta_i) / (eta * eta);
+    if sin2_theta_t >= 1.0 {
+        return 1.0;
+    }
+    let cos_theta_t = (1.0 - sin2_theta_t).sqrt();
+    let r_parallel = (eta * cos_theta_i - cos_theta_t) / (eta * cos_theta_i + cos_theta_t);
+    let r_perpendicular = (cos_theta_i - eta * cos_theta_t) / (cos_theta_i + eta * cos_theta_t);
+    return (r_parallel * r_parallel + r_perpendicular * r_perpendicular) / 2.0;
+}
+
+// Schlick's approximation of Fresnel reflectance, given the reflectance at
+// normal incidence.
+#[inline]
+pub fn fresnel_schlick(cos_theta_i: float, f0: &Color) -> Color {
+    let weight = (1.0 - cos_theta_i.clamp(0.0, 1.0)).powi(5);
+    f0 + (1.0 - f0) * weight
+}
+
+// Fresnel reflectance of a conductor with complex index of refraction
+// eta + i k, evaluated per channel.
+pub fn fresnel_conductor(cos_theta_i: float, eta: &Color, k: &Color) -> Color {
+    let cos_theta_i = cos_theta_i.clamp(0.0, 1.0);
+    let cos2 = cos_theta_i * cos_theta_i;
+    let sin2 = 1.0 - cos2;
+    let e = [0, 1, 2].map(|c| {
+        let (eta, k) = (eta.e[c], k.e[c]);
+        let (eta2, k2) = (eta * eta, k * k);
+        let t0 = eta2 - k2 - sin2;
+        let a2_plus_b2 = (t0 * t0 + 4.0 * eta2 * k2).sqrt();
+        let t1 = a2_plus_b2 + cos2;
+        let a = (0.5 * (a2_plus_b2 + t0)).max(0.0).sqrt();
+        let t2 = 2.0 * cos_theta_i * a;
+        let r_s = (t1 - t2) / (t1 + t2);
+        let t3 = cos2 * a2_plus_b2 + sin2 * sin2;
+        let t4 = t2 * sin2;
+        let r_p = r_s * (t3 - t4) / (t3 + t4);
+        (r_s + r_p) / 2.0
+    });
+    return Color::from_array(e);
+}
+
+// Refracts `wo` (pointing away from the surface, on the same side as
+// `normal`) through an interface with relative index `eta` = n_t / n_i.
+// Returns `None` on total internal reflection.
+pub fn refract(wo: &Vec3, normal: &Vec3, eta: float) -> Option<Vec3> {
+    let cos_theta_i = normal.dot(wo);
+    let sin2_theta_i = (1.0 - cos_theta_i * cos_theta_i).max(0.0);
+    let sin2_theta_t = sin2_theta_i / (eta * eta);
+    if sin2_theta_t >= 1.0 {
+        return None;
+    }
+    let cos_theta_t = (1.0 - sin2_theta_t).sqrt();
+    return Some(-wo / eta + (cos_theta_i / eta - cos_theta_t) * normal);
+}
@@ -0,0 +1,242 @@
+use std::fmt::Debug;
+
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use crate::{prelude::*, texture::Texture};
+
+const POINT_COUNT: usize = 256;
+
+// Gradient noise with the quintic fade of Perlin's "Improving Noise", but
+// random unit gradients drawn from a seeded generator so results are
+// reproducible.
+pub struct Perlin {
+    seed: u64,
+    gradients: Vec<Vec3>,
+    perm_x: Vec<usize>,
+    perm_y: Vec<usize>,
+    perm_z: Vec<usize>,
+}
+
+impl Perlin {
+    pub fn new(seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let gradients = (0..POINT_COUNT)
+            .map(|_| {
+                Vec3::new(
+                    rng.gen_range(-1.0..1.0),
+                    rng.gen_range(-1.0..1.0),
+                    rng.gen_range(-1.0..1.0),
+                )
+                .unit()
+            })
+            .collect();
+        let mut permutation = || {
+            let mut p: Vec<usize> = (0..POINT_COUNT).collect();
+            p.shuffle(&mut rng);
+            p
+        };
+        Self {
+            seed,
+            gradients,
+            perm_x: permutation(),
+            perm_y: permutation(),
+            perm_z: permutation(),
+        }
+    }
+
+    // Returns a value in roughly [-1, 1].
+    pub fn noise(&self, point: &Point3) -> float {
+        let f = point.map(|c| c - c.floor());
+        let cell = point.map(|c| c.floor());
+        let (i, j, k) = (cell.x() as i64, cell.y() as i64, cell.z() as i64);
+        let smooth = f.map(|t| t * t * t * (t * (6.0 * t - 15.0) + 10.0));
+
+        let mut accumulator = 0.0;
+        for di in 0..2 {
+            for dj in 0..2 {
+                for dk in 0..2 {
+                    let gradient = self.gradients[self.perm_x[((i + di) & 255) as usize]
+                        ^ self.perm_y[((j + dj) & 255) as usize]
+                        ^ self.perm_z[((k + dk) & 255) as usize]];
+                    let corner = Vec3::new(di as float, dj as float, dk as float);
+                    let weight = (corner * smooth + (1.0 - corner) * (1.0 - smooth))
+                        .e
+                        .iter()
+                        .product::<float>();
+                    accumulator += weight * gradient.dot(&(f - corner));
+                }
+            }
+        }
+        return accumulator;
+    }
+
+    // Sum of absolute octaves; always positive, with sharp creases.
+    pub fn turbulence(&self, point: &Point3, octaves: usize) -> float {
+        let mut accumulator = 0.0;
+        let mut point = *point;
+        let mut weight = 1.0;
+        for _ in 0..octaves {
+            accumulator += weight * self.noise(&point).abs();
+            weight *= 0.5;
+            point = 2.0 * point;
+        }
+        return accumulator;
+    }
+
+    // Fractional Brownian motion: signed octaves of noise at increasing
+    // frequency (`lacunarity`) and decreasing amplitude (`gain`).
+    pub fn fbm(&self, point: &Point3, octaves: usize, lacunarity: float, gain: float) -> float {
+        let mut accumulator = 0.0;
+        let mut point = *point;
+        let mut weight = 1.0;
+        for _ in 0..octaves {
+            accumulator += weight * self.noise(&point);
+            weight *= gain;
+            point = lacunarity * point;
+        }
+        return accumulator;
+    }
+}
+
+impl Debug for Perlin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Perlin").field("seed", &self.seed).finish()
+    }
+}
+
+// Cellular noise with one feature point per unit cell, placed by hashing the
+// cell coordinates with the seed.
+#[derive(Debug)]
+pub struct Worley {
+    seed: u64,
+}
+
+impl Worley {
+    pub fn new(seed: u64) -> Self {
+        Self { seed }
+    }
+
+    fn feature_point(&self, i: i64, j: i64, k: i64) -> Point3 {
+        let mut hash = mix_bits(self.seed);
+        for c in [i, j, k] {
+            hash = mix_bits(hash ^ c as u64);
+        }
+        let offset = Vec3::new(
+            hash_to_norm(hash),
+            hash_to_norm(mix_bits(hash ^ 1)),
+            hash_to_norm(mix_bits(hash ^ 2)),
+        );
+        Point3::new(i as float, j as float, k as float) + offset
+    }
+
+    // Distances to the nearest and second nearest feature points.
+    pub fn distances(&self, point: &Point3) -> (float, float) {
+        let cell = point.map(|c| c.floor());
+        let (i, j, k) = (cell.x() as i64, cell.y() as i64, cell.z() as i64);
+        let (mut f1, mut f2) = (float::INFINITY, float::INFINITY);
+        for di in -1..=1 {
+            for dj in -1..=1 {
+                for dk in -1..=1 {
+                    let d = (self.feature_point(i + di, j + dj, k + dk) - point).lenght();
+                    if d < f1 {
+                        f2 = f1;
+                        f1 = d;
+                    } else if d < f2 {
+                        f2 = d;
+                    }
+                }
+            }
+        }
+        (f1, f2)
+    }
+}
+
+#[derive(Debug)]
+enum Pattern {
+    Perlin(Perlin),
+    Turbulence(Perlin, usize),
+    Fbm(Perlin, usize, float, float),
+    Marble(Perlin, usize),
+    Worley(Worley),
+}
+
+// Maps a scalar noise pattern in [0, 1] onto a gradient between two colors,
+// black to white unless set with `with_colors`.
+#[derive(Debug)]
+pub struct NoiseTexture {
+    pattern: Pattern,
+    scale: float,
+    low: Color,
+    high: Color,
+}
+
+impl NoiseTexture {
+    fn new(pattern: Pattern, scale: float) -> Self {
+        Self {
+            pattern,
+            scale,
+            low: Color::ZERO,
+            high: Color::uniform(1.0),
+        }
+    }
+
+    pub fn perlin(seed: u64, scale: float) -> Self {
+        Self::new(Pattern::Perlin(Perlin::new(seed)), scale)
+    }
+
+    pub fn turbulence(seed: u64, scale: float, octaves: usize) -> Self {
+        Self::new(Pattern::Turbulence(Perlin::new(seed), octaves), scale)
+    }
+
+    pub fn fbm(seed: u64, scale: float, octaves: usize) -> Self {
+        Self::new(Pattern::Fbm(Perlin::new(seed), octaves, 2.0, 0.5), scale)
+    }
+
+    pub fn marble(seed: u64, scale: float, octaves: usize) -> Self {
+        Self::new(Pattern::Marble(Perlin::new(seed), octaves), scale)
+    }
+
+    pub fn worley(seed: u64, scale: float) -> Self {
+        Self::new(Pattern::Worley(Worley::new(seed)), scale)
+    }
+
+    #[inline]
+    pub fn with_colors(mut self, low: Color, high: Color) -> Self {
+        self.low = low;
+        self.high = high;
+        self
+    }
+
+    // Only affects fBm patterns.
+    #[inline]
+    pub fn with_lacunarity_gain(mut self, lacunarity: float, gain: float) -> Self {
+        if let Pattern::Fbm(_, _, l, g) = &mut self.pattern {
+            *l = lacunarity;
+            *g = gain;
+        }
+        self
+    }
+
+    fn intensity(&self, point: &Point3) -> float {
+        let p = self.scale * point;
+        let t = match &self.pattern {
+            Pattern::Perlin(perlin) => 0.5 * (1.0 + perlin.noise(&p)),
+            Pattern::Turbulence(perlin, octaves) => perlin.turbulence(&p, *octaves),
+            Pattern::Fbm(perlin, octaves, lacunarity, gain) => {
+                0.5 * (1.0 + perlin.fbm(&p, *octaves, *lacunarity, *gain))
+            }
+            Pattern::Marble(perlin, octaves) => {
+                0.5 * (1.0 + (p.z() + 10.0 * perlin.turbulence(&p, *octaves)).sin())
+            }
+            Pattern::Worley(worley) => worley.distances(&p).0,
+        };
+        return t.clamp(0.0, 1.0);
+    }
+}
+
+impl Texture for NoiseTexture {
+    fn value(&self, _u: float, _v: float, position: &Point3) -> Color {
+        let t = self.intensity(position);
+        (1.0 - t) * self.low + t * self.high
+    }
+}
@@ -0,0 +1,141 @@
+use std::rc::Rc;
+
+use crate::{
+    hittable::HitRecord, material::Material, prelude::*, sampler::SampleContext, texture::Texture,
+};
+
+// Perturbs the shading normal with a tangent space normal map before
+// scattering with `base`. Map colors encode (x, y, z) as (r, g, b) * 2 - 1,
+// with x along dp/du and z along the surface normal, so the map should be
+// loaded without gamma decoding.
+#[derive(Debug)]
+pub struct NormalMapped {
+    base: Rc<dyn Material>,
+    map: Rc<dyn Texture>,
+    strength: float,
+}
+
+impl NormalMapped {
+    pub fn new(base: Rc<dyn Material>, map: Rc<dyn Texture>) -> Self {
+        Self {
+            base,
+            map,
+            strength: 1.0,
+        }
+    }
+
+    // Scales the tangent space deflection; 0 leaves the normal untouched.
+    #[inline]
+    pub fn with_strength(mut self, strength: float) -> Self {
+        self.strength = strength;
+        self
+    }
+}
+
+impl Material for NormalMapped {
+    fn scatter(
+        &self,
+        in_ray: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut SampleContext,
+    ) -> Option<(Color, Ray)> {
+        let sample = self
+            .map
+            .value(hit_record.u, hit_record.v, &hit_record.position);
+        let local = 2.0 * sample - 1.0;
+        let local = Vec3::new(
+            self.strength * local.x(),
+            self.strength * local.y(),
+            local.z().max(1e-3),
+        );
+
+        let normal = hit_record.outward_normal();
+        let mut frame = Frame::from_normal_tangent(&normal, &hit_record.dpdu);
+        if frame.y.dot(&hit_record.dpdv) < 0.0 {
+            frame.y = -frame.y;
+        }
+        let perturbed = frame.to_world(&local).unit();
+        return scatter_with_shading_normal(&*self.base, in_ray, hit_record, perturbed, sampler);
+    }
+
+    fn emitted(&self, in_ray: &Ray, hit_record: &HitRecord) -> Color {
+        self.base.emitted(in_ray, hit_record)
+    }
+}
+
+// Perturbs the shading normal as if the surface were displaced along it by
+// `scale` times the scalar value of `height`.
+#[derive(Debug)]
+pub struct BumpMapped {
+    base: Rc<dyn Material>,
+    height: Rc<dyn Texture>,
+    scale: float,
+}
+
+impl BumpMapped {
+    pub fn new(base: Rc<dyn Material>, height: Rc<dyn Texture>, scale: float) -> Self {
+        Self {
+            base,
+            height,
+            scale,
+        }
+    }
+
+    fn displacement(&self, u: float, v: float, position: &Point3) -> float {
+        self.scale * self.height.scalar_value(u, v, position)
+    }
+}
+
+impl Material for BumpMapped {
+    fn scatter(
+        &self,
+        in_ray: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut SampleContext,
+    ) -> Option<(Color, Ray)> {
+        const DELTA: float = 5e-4;
+        let (u, v, p) = (hit_record.u, hit_record.v, hit_record.position);
+        let (dpdu, dpdv) = (hit_record.dpdu, hit_record.dpdv);
+        let normal = hit_record.outward_normal();
+
+        let displacement = self.displacement(u, v, &p);
+        let du = (self.displacement(u + DELTA, v, &(p + DELTA * dpdu)) - displacement) / DELTA;
+        let dv = (self.displacement(u, v + DELTA, &(p + DELTA * dpdv)) - displacement) / DELTA;
+
+        let perturbed = (dpdu + du * normal).cross(&(dpdv + dv * normal));
+        if perturbed.near_zero() {
+            return self.base.scatter(in_ray, hit_record, sampler);
+        }
+        let perturbed = perturbed.unit();
+        let perturbed = if perturbed.dot(&normal) < 0.0 {
+            -perturbed
+        } else {
+            perturbed
+        };
+        return scatter_with_shading_normal(&*self.base, in_ray, hit_record, perturbed, sampler);
+    }
+
+    fn emitted(&self, in_ray: &Ray, hit_record: &HitRecord) -> Color {
+        self.base.emitted(in_ray, hit_record)
+    }
+}
+
+// Scatters with a replaced shading normal, discarding paths that the shading
+// normal sends to the other side of the geometric surface than intended,
+// which would otherwise leak light through it.
+fn scatter_with_shading_normal(
+    base: &dyn Material,
+    in_ray: &Ray,
+    hit_record: &HitRecord,
+    outward_shading_normal: Vec3,
+    sampler: &mut SampleContext,
+) -> Option<(Color, Ray)> {
+    let mut shaded = hit_record.clone();
+    shaded.set_outward_shading_normal(outward_shading_normal);
+
+    let (attenuation, scattered) = base.scatter(in_ray, &shaded, sampler)?;
+    let direction = scattered.direction();
+    let shading_side = direction.dot(&shaded.normal) > 0.0;
+    let geometric_side = direction.dot(&shaded.geometric_normal) > 0.0;
+    return (shading_side == geometric_side).then_some((attenuation, scattered));
+}
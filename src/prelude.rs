@@ -1,3 +1,6 @@
+pub use crate::frame::*;
+pub use crate::matrix::*;
+pub use crate::quat::*;
 #[allow(unused_imports)]
 pub use crate::random::*;
 pub use crate::ray::*;
@@ -6,4 +9,6 @@ pub use crate::vec3::*;
 #[allow(non_camel_case_types)]
 pub type float = f32;
 
+pub const PI: float = std::f64::consts::PI as float;
+
 pub type Color = Vec3;
@@ -0,0 +1,224 @@
+use std::rc::Rc;
+
+use crate::{
+    hittable::HitRecord,
+    material::Material,
+    microfacet::{fresnel_schlick, TrowbridgeReitz},
+    prelude::*,
+    sampler::SampleContext,
+    texture::{SolidColor, Texture},
+};
+
+// An artist facing uber material loosely following Blender's Principled BSDF
+// (itself based on the Disney BRDF). Lobes are layered from top to bottom as
+// clearcoat, then either metal or a dielectric base split into glass
+// transmission and specular over diffuse with sheen. One lobe is picked per
+// scatter in proportion to how much energy reaches it.
+#[derive(Debug)]
+pub struct Principled {
+    base_color: Rc<dyn Texture>,
+    metallic: Rc<dyn Texture>,
+    roughness: Rc<dyn Texture>,
+    specular: Rc<dyn Texture>,
+    sheen: Rc<dyn Texture>,
+    sheen_tint: Rc<dyn Texture>,
+    clearcoat: Rc<dyn Texture>,
+    clearcoat_roughness: Rc<dyn Texture>,
+    transmission: Rc<dyn Texture>,
+    emission: Rc<dyn Texture>,
+    emission_strength: float,
+    index_of_refraction: float,
+}
+
+macro_rules! texture_param {
+    ($field:ident, $func:ident, $texture_func:ident) => {
+        #[inline]
+        pub fn $func(self, value: float) -> Self {
+            self.$texture_func(constant(Color::uniform(value)))
+        }
+
+        #[inline]
+        pub fn $texture_func(mut self, texture: Rc<dyn Texture>) -> Self {
+            self.$field = texture;
+            self
+        }
+    };
+}
+
+fn constant(value: Color) -> Rc<dyn Texture> {
+    Rc::new(SolidColor::new(value))
+}
+
+impl Principled {
+    pub fn new(base_color: Color) -> Self {
+        Self::from_texture(constant(base_color))
+    }
+
+    pub fn from_texture(base_color: Rc<dyn Texture>) -> Self {
+        Self {
+            base_color,
+            metallic: constant(Color::ZERO),
+            roughness: constant(Color::uniform(0.5)),
+            specular: constant(Color::uniform(0.5)),
+            sheen: constant(Color::ZERO),
+            sheen_tint: constant(Color::uniform(0.5)),
+            clearcoat: constant(Color::ZERO),
+            clearcoat_roughness: constant(Color::uniform(0.03)),
+            transmission: constant(Color::ZERO),
+            emission: constant(Color::ZERO),
+            emission_strength: 1.0,
+            index_of_refraction: 1.45,
+        }
+    }
+
+    texture_param!(metallic, with_metallic, with_metallic_texture);
+    texture_param!(roughness, with_roughness, with_roughness_texture);
+    texture_param!(specular, with_specular, with_specular_texture);
+    texture_param!(sheen, with_sheen, with_sheen_texture);
+    texture_param!(sheen_tint, with_sheen_tint, with_sheen_tint_texture);
+    texture_param!(clearcoat, with_clearcoat, with_clearcoat_texture);
+    texture_param!(
+        clearcoat_roughness,
+        with_clearcoat_roughness,
+        with_clearcoat_roughness_texture
+    );
+    texture_param!(transmission, with_transmission, with_transmission_texture);
+
+    #[inline]
+    pub fn with_emission(self, color: Color, strength: float) -> Self {
+        self.with_emission_texture(constant(color), strength)
+    }
+
+    #[inline]
+    pub fn with_emission_texture(mut self, emission: Rc<dyn Texture>, strength: float) -> Self {
+        self.emission = emission;
+        self.emission_strength = strength;
+        self
+    }
+
+    #[inline]
+    pub fn with_index_of_refraction(mut self, index_of_refraction: float) -> Self {
+        self.index_of_refraction = index_of_refraction;
+        self
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Lobe {
+    Clearcoat,
+    Metal,
+    Glass,
+    Specular,
+    Diffuse,
+}
+
+impl Material for Principled {
+    fn scatter(
+        &self,
+        in_ray: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut SampleContext,
+    ) -> Option<(Color, Ray)> {
+        let (u, v, p) = (hit_record.u, hit_record.v, hit_record.position);
+        let scalar = |texture: &Rc<dyn Texture>| texture.scalar_value(u, v, &p).clamp(0.0, 1.0);
+        let base_color = self.base_color.value(u, v, &p);
+        let metallic = scalar(&self.metallic);
+        let roughness = scalar(&self.roughness);
+        let transmission = scalar(&self.transmission);
+        let clearcoat = scalar(&self.clearcoat);
+
+        let frame = Frame::from_normal(&hit_record.normal);
+        let wo = frame.to_local(&-in_ray.direction().unit());
+        if wo.z() <= 0.0 {
+            return None;
+        }
+
+        // Only the glass lobe can be reached from inside the object.
+        let lobe = if !hit_record.front_face && transmission > 0.0 {
+            Lobe::Glass
+        } else {
+            // These weights sum to one, so picking a lobe with probability
+            // equal to its weight leaves each lobe's own sample weight as is.
+            let specular_f0 = Color::uniform(0.08 * scalar(&self.specular));
+            let clearcoat_weight = clearcoat * fresnel_schlick(wo.z(), &Color::uniform(0.04)).r();
+            let specular_weight = fresnel_schlick(wo.z(), &specular_f0).r();
+            let base_weight = 1.0 - clearcoat_weight;
+            let dielectric_weight = base_weight * (1.0 - metallic);
+            let opaque_weight = dielectric_weight * (1.0 - transmission);
+            let weights = [
+                (Lobe::Clearcoat, clearcoat_weight),
+                (Lobe::Metal, base_weight * metallic),
+                (Lobe::Glass, dielectric_weight * transmission),
+                (Lobe::Specular, opaque_weight * specular_weight),
+                (Lobe::Diffuse, opaque_weight * (1.0 - specular_weight)),
+            ];
+
+            let mut choice = sampler.next_1d();
+            weights
+                .iter()
+                .find(|(_, weight)| {
+                    choice -= weight;
+                    choice < 0.0
+                })
+                .map_or(Lobe::Diffuse, |(lobe, _)| *lobe)
+        };
+
+        let distribution = TrowbridgeReitz::from_roughness(roughness);
+        let (wi, attenuation) = match lobe {
+            Lobe::Clearcoat => {
+                let distribution =
+                    TrowbridgeReitz::from_roughness(scalar(&self.clearcoat_roughness));
+                let (wi, _, weight) = distribution.sample_reflection(&wo, sampler)?;
+                (wi, Color::uniform(weight))
+            }
+            Lobe::Metal => {
+                let (wi, wm, weight) = distribution.sample_reflection(&wo, sampler)?;
+                (wi, weight * fresnel_schlick(wo.dot(&wm), &base_color))
+            }
+            Lobe::Glass => {
+                let eta = if hit_record.front_face {
+                    self.index_of_refraction
+                } else {
+                    1.0 / self.index_of_refraction
+                };
+                let (wi, weight) = distribution.sample_dielectric(&wo, eta, sampler)?;
+                let tint = if hit_record.front_face && wi.z() < 0.0 {
+                    base_color
+                } else {
+                    Color::uniform(1.0)
+                };
+                (wi, weight * tint)
+            }
+            Lobe::Specular => {
+                let (wi, _, weight) = distribution.sample_reflection(&wo, sampler)?;
+                (wi, Color::uniform(weight))
+            }
+            Lobe::Diffuse => {
+                let wi = Vec3::random_cosine_direction(sampler);
+                let sheen = scalar(&self.sheen);
+                let sheen_tint = scalar(&self.sheen_tint);
+                let tint = if base_color.luminance() > 0.0 {
+                    base_color / base_color.luminance()
+                } else {
+                    Color::uniform(1.0)
+                };
+                let sheen_color = (1.0 - sheen_tint) + sheen_tint * tint;
+                let cos_theta_d = wi.dot(&(wi + wo).unit());
+                let sheen_weight = PI * sheen * (1.0 - cos_theta_d).powi(5);
+                (wi, base_color + sheen_weight * sheen_color)
+            }
+        };
+
+        return Some((
+            attenuation,
+            in_ray.spawn(hit_record.position, frame.to_world(&wi)),
+        ));
+    }
+
+    fn emitted(&self, _in_ray: &Ray, hit_record: &HitRecord) -> Color {
+        self.emission_strength
+            * self
+                .emission
+                .value(hit_record.u, hit_record.v, &hit_record.position)
+    }
+}
@@ -0,0 +1,310 @@
+use std::{
+    fmt::Debug,
+    ops::{Add, Mul, Neg, Sub},
+};
+
+use crate::prelude::*;
+
+#[derive(Clone, Copy)]
+pub struct Quat {
+    pub v: Vec3,
+    pub w: float,
+}
+
+impl Default for Quat {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Quat {
+    pub const IDENTITY: Quat = Quat::new(0.0, 0.0, 0.0, 1.0);
+
+    #[inline]
+    pub const fn new(x: float, y: float, z: float, w: float) -> Self {
+        Self {
+            v: Vec3::new(x, y, z),
+            w,
+        }
+    }
+
+    #[inline]
+    pub fn from_axis_angle(axis: &Vec3, angle: float) -> Self {
+        let (sin, cos) = (angle / 2.0).sin_cos();
+        Self {
+            v: sin * axis.unit(),
+            w: cos,
+        }
+    }
+
+    pub fn from_mat3(m: &Mat3) -> Self {
+        let m = m.m;
+        let trace = m[0][0] + m[1][1] + m[2][2];
+        let q = if trace > 0.0 {
+            let s = 2.0 * (trace + 1.0).sqrt();
+            Self::new(
+                (m[2][1] - m[1][2]) / s,
+                (m[0][2] - m[2][0]) / s,
+                (m[1][0] - m[0][1]) / s,
+                0.25 * s,
+            )
+        } else if m[0][0] > m[1][1] && m[0][0] > m[2][2] {
+            let s = 2.0 * (1.0 + m[0][0] - m[1][1] - m[2][2]).sqrt();
+            Self::new(
+                0.25 * s,
+                (m[0][1] + m[1][0]) / s,
+                (m[0][2] + m[2][0]) / s,
+                (m[2][1] - m[1][2]) / s,
+            )
+        } else if m[1][1] > m[2][2] {
+            let s = 2.0 * (1.0 + m[1][1] - m[0][0] - m[2][2]).sqrt();
+            Self::new(
+                (m[0][1] + m[1][0]) / s,
+                0.25 * s,
+                (m[1][2] + m[2][1]) / s,
+                (m[0][2] - m[2][0]) / s,
+            )
+        } else {
+            let s = 2.0 * (1.0 + m[2][2] - m[0][0] - m[1][1]).sqrt();
+            Self::new(
+                (m[0][2] + m[2][0]) / s,
+                (m[1][2] + m[2][1]) / s,
+                0.25 * s,
+                (m[1][0] - m[0][1]) / s,
+            )
+        };
+        return q.unit();
+    }
+
+    #[inline]
+    pub fn dot(&self, other: &Quat) -> float {
+        self.v.dot(&other.v) + self.w * other.w
+    }
+
+    #[inline]
+    pub fn lenght_squared(&self) -> float {
+        self.dot(self)
+    }
+
+    #[inline]
+    pub fn lenght(&self) -> float {
+        self.dot(self).sqrt()
+    }
+
+    #[inline]
+    pub fn unit(&self) -> Quat {
+        self * (1.0 / self.lenght())
+    }
+
+    #[inline]
+    pub fn conjugate(&self) -> Quat {
+        Self {
+            v: -self.v,
+            w: self.w,
+        }
+    }
+
+    #[inline]
+    pub fn inverse(&self) -> Quat {
+        self.conjugate() * (1.0 / self.lenght_squared())
+    }
+
+    #[inline]
+    pub fn rotate(&self, vector: &Vec3) -> Vec3 {
+        let t = 2.0 * self.v.cross(vector);
+        vector + self.w * t + self.v.cross(&t)
+    }
+
+    pub fn slerp(&self, other: &Quat, t: float) -> Quat {
+        let mut other = *other;
+        let mut cos_theta = self.dot(&other);
+        if cos_theta < 0.0 {
+            other = -other;
+            cos_theta = -cos_theta;
+        }
+
+        if cos_theta > 0.9995 {
+            return (self * (1.0 - t) + other * t).unit();
+        }
+
+        let theta = cos_theta.acos();
+        let sin_theta = theta.sin();
+        let a = ((1.0 - t) * theta).sin() / sin_theta;
+        let b = (t * theta).sin() / sin_theta;
+        return self * a + other * b;
+    }
+
+    pub fn to_mat3(&self) -> Mat3 {
+        let (x, y, z, w) = (self.v.x(), self.v.y(), self.v.z(), self.w);
+        Mat3::from_rows([
+            [
+                1.0 - 2.0 * (y * y + z * z),
+                2.0 * (x * y - z * w),
+                2.0 * (x * z + y * w),
+            ],
+            [
+                2.0 * (x * y + z * w),
+                1.0 - 2.0 * (x * x + z * z),
+                2.0 * (y * z - x * w),
+            ],
+            [
+                2.0 * (x * z - y * w),
+                2.0 * (y * z + x * w),
+                1.0 - 2.0 * (x * x + y * y),
+            ],
+        ])
+    }
+
+    #[inline]
+    pub fn to_mat4(&self) -> Mat4 {
+        Mat4::from_linear_translation(self.to_mat3(), Vec3::ZERO)
+    }
+}
+
+impl Debug for Quat {
+    #[inline]
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(&[self.v.x(), self.v.y(), self.v.z(), self.w], f)
+    }
+}
+
+impl Neg for Quat {
+    type Output = Self;
+
+    #[inline]
+    fn neg(self) -> Self::Output {
+        Self {
+            v: -self.v,
+            w: -self.w,
+        }
+    }
+}
+
+macro_rules! impl_borrowed_quat_bop {
+    ($lhs:ty, $rhs:ty, $output:ty, $trait:ident, $func:ident) => {
+        impl $trait<&$rhs> for &$lhs {
+            type Output = $output;
+
+            #[inline]
+            fn $func(self, rhs: &$rhs) -> Self::Output {
+                $trait::$func(*self, *rhs)
+            }
+        }
+
+        impl $trait<&$rhs> for $lhs {
+            type Output = $output;
+
+            #[inline]
+            fn $func(self, rhs: &$rhs) -> Self::Output {
+                $trait::$func(self, *rhs)
+            }
+        }
+
+        impl $trait<$rhs> for &$lhs {
+            type Output = $output;
+
+            #[inline]
+            fn $func(self, rhs: $rhs) -> Self::Output {
+                $trait::$func(*self, rhs)
+            }
+        }
+    };
+}
+
+macro_rules! impl_quat_element_wise_op {
+    ($trait:ident, $func:ident) => {
+        impl $trait for Quat {
+            type Output = Self;
+
+            #[inline]
+            fn $func(self, rhs: Self) -> Self::Output {
+                Self {
+                    v: $trait::$func(self.v, rhs.v),
+                    w: $trait::$func(self.w, rhs.w),
+                }
+            }
+        }
+        impl_borrowed_quat_bop!(Quat, Quat, Quat, $trait, $func);
+    };
+}
+
+impl_quat_element_wise_op!(Add, add);
+impl_quat_element_wise_op!(Sub, sub);
+
+impl Mul<float> for Quat {
+    type Output = Self;
+
+    #[inline]
+    fn mul(self, rhs: float) -> Self::Output {
+        Self {
+            v: self.v * rhs,
+            w: self.w * rhs,
+        }
+    }
+}
+impl_borrowed_quat_bop!(Quat, float, Quat, Mul, mul);
+
+impl Mul for Quat {
+    type Output = Self;
+
+    #[inline]
+    fn mul(self, rhs: Self) -> Self::Output {
+        Self {
+            v: self.w * rhs.v + rhs.w * self.v + self.v.cross(&rhs.v),
+            w: self.w * rhs.w - self.v.dot(&rhs.v),
+        }
+    }
+}
+impl_borrowed_quat_bop!(Quat, Quat, Quat, Mul, mul);
+
+impl Mul<Vec3> for Quat {
+    type Output = Vec3;
+
+    #[inline]
+    fn mul(self, rhs: Vec3) -> Self::Output {
+        self.rotate(&rhs)
+    }
+}
+impl_borrowed_quat_bop!(Quat, Vec3, Vec3, Mul, mul);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // q and -q are the same rotation.
+    fn assert_same_rotation(a: &Quat, b: &Quat) {
+        assert!((a.dot(b).abs() - 1.0).abs() < 1e-5, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn slerp_interpolates_the_angle() {
+        let start = Quat::from_axis_angle(&Vec3::Y, 0.2);
+        let end = Quat::from_axis_angle(&Vec3::Y, 1.6);
+        assert_same_rotation(&start.slerp(&end, 0.0), &start);
+        assert_same_rotation(&start.slerp(&end, 1.0), &end);
+        assert_same_rotation(
+            &start.slerp(&end, 0.5),
+            &Quat::from_axis_angle(&Vec3::Y, 0.9),
+        );
+        // The shorter way round, even when the quaternions are on opposite
+        // hemispheres.
+        assert_same_rotation(
+            &start.slerp(&-end, 0.5),
+            &Quat::from_axis_angle(&Vec3::Y, 0.9),
+        );
+    }
+
+    #[test]
+    fn matrix_matches_axis_angle_rotation() {
+        let axis = Vec3::new(1.0, -2.0, 0.5).unit();
+        for angle in [0.0, 0.4, 2.5, -1.2] {
+            let from_quat = Quat::from_axis_angle(&axis, angle).to_mat3();
+            let expected = Mat3::rotation(&axis, angle);
+            for (row_a, row_b) in from_quat.m.iter().zip(&expected.m) {
+                for (a, b) in row_a.iter().zip(row_b) {
+                    assert!((a - b).abs() < 1e-5, "{:?} != {:?}", from_quat, expected);
+                }
+            }
+        }
+    }
+}
@@ -7,3 +7,17 @@ pub fn rand_norm() -> float {
 pub fn rand(min: float, max: float) -> float {
     min + (max - min) * rand_norm()
 }
+
+// The splitmix64 finalizer: a cheap, well distributed hash of a 64-bit value.
+#[inline]
+pub fn mix_bits(mut v: u64) -> u64 {
+    v = (v ^ (v >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    v = (v ^ (v >> 27)).wrapping_mul(0x94d049bb133111eb);
+    v ^ (v >> 31)
+}
+
+// Maps the top bits of a hash to a float in [0, 1).
+#[inline]
+pub fn hash_to_norm(hash: u64) -> float {
+    (hash >> 40) as float / (1u64 << 24) as float
+}
@@ -4,11 +4,41 @@ use crate::prelude::*;
 pub struct Ray {
     origin: Point3,
     direction: Vec3,
+    time: float,
+    wavelength: Option<float>,
 }
 
 impl Ray {
     pub fn new(origin: Point3, direction: Vec3) -> Self {
-        Self { origin, direction }
+        Self::new_with_time(origin, direction, 0.0)
+    }
+
+    pub fn new_with_time(origin: Point3, direction: Vec3, time: float) -> Self {
+        Self {
+            origin,
+            direction,
+            time,
+            wavelength: None,
+        }
+    }
+
+    // Tags the ray with a single wavelength in nanometers, for spectral
+    // rendering.
+    #[inline]
+    pub fn with_wavelength(mut self, wavelength: float) -> Self {
+        self.wavelength = Some(wavelength);
+        self
+    }
+
+    // Starts a new ray that shares this ray's time and wavelength, e.g. when
+    // scattering.
+    pub fn spawn(&self, origin: Point3, direction: Vec3) -> Self {
+        Self {
+            origin,
+            direction,
+            time: self.time,
+            wavelength: self.wavelength,
+        }
     }
 
     pub fn origin(&self) -> Point3 {
@@ -19,6 +49,14 @@ impl Ray {
         self.direction
     }
 
+    pub fn time(&self) -> float {
+        self.time
+    }
+
+    pub fn wavelength(&self) -> Option<float> {
+        self.wavelength
+    }
+
     pub fn at(&self, t: float) -> Point3 {
         self.origin + self.direction * t
     }
@@ -0,0 +1,98 @@
+use std::rc::Rc;
+
+use crate::{
+    hittable::HitRecord,
+    material::Material,
+    microfacet::TrowbridgeReitz,
+    prelude::*,
+    sampler::SampleContext,
+    spectrum::Ior,
+    texture::{SolidColor, Texture},
+};
+
+// Frosted glass: GGX microfacet reflection and transmission weighted by the
+// exact dielectric Fresnel term, with optional Beer-Lambert absorption inside
+// the medium.
+#[derive(Debug)]
+pub struct RoughDielectric {
+    index_of_refraction: Ior,
+    roughness: Rc<dyn Texture>,
+    absorption: Color,
+}
+
+impl RoughDielectric {
+    pub fn new(index_of_refraction: float, roughness: float) -> Self {
+        Self::from_texture(
+            index_of_refraction,
+            Rc::new(SolidColor::new(Color::uniform(roughness))),
+        )
+    }
+
+    pub fn from_texture(index_of_refraction: float, roughness: Rc<dyn Texture>) -> Self {
+        Self {
+            index_of_refraction: Ior::Constant(index_of_refraction),
+            roughness,
+            absorption: Color::ZERO,
+        }
+    }
+
+    // Replaces the index of refraction with a wavelength dependent one.
+    #[inline]
+    pub fn with_ior(mut self, index_of_refraction: Ior) -> Self {
+        self.index_of_refraction = index_of_refraction;
+        self
+    }
+
+    // Absorption coefficient per unit of distance travelled inside.
+    #[inline]
+    pub fn with_absorption(mut self, absorption: Color) -> Self {
+        self.absorption = absorption;
+        self
+    }
+
+    // Absorption such that light keeps `tint` of its energy after travelling
+    // `distance` inside the medium.
+    #[inline]
+    pub fn with_tint(self, tint: Color, distance: float) -> Self {
+        self.with_absorption(tint.map(|c| -c.max(1e-6).ln() / distance))
+    }
+}
+
+impl Material for RoughDielectric {
+    fn scatter(
+        &self,
+        in_ray: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut SampleContext,
+    ) -> Option<(Color, Ray)> {
+        // Exiting rays have travelled through the medium since the last event.
+        let transmittance = if hit_record.front_face {
+            Color::uniform(1.0)
+        } else {
+            let distance = hit_record.t * in_ray.direction().lenght();
+            (-self.absorption * distance).map(float::exp)
+        };
+        let index_of_refraction = self.index_of_refraction.at(in_ray.wavelength());
+        let eta = if hit_record.front_face {
+            index_of_refraction
+        } else {
+            1.0 / index_of_refraction
+        };
+
+        let frame = Frame::from_normal(&hit_record.normal);
+        let wo = frame.to_local(&-in_ray.direction().unit());
+        if wo.z() <= 0.0 {
+            return None;
+        }
+
+        let roughness =
+            self.roughness
+                .scalar_value(hit_record.u, hit_record.v, &hit_record.position);
+        let distribution = TrowbridgeReitz::from_roughness(roughness);
+        let (wi, weight) = distribution.sample_dielectric(&wo, eta, sampler)?;
+        return Some((
+            weight * transmittance,
+            in_ray.spawn(hit_record.position, frame.to_world(&wi)),
+        ));
+    }
+}
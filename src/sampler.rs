@@ -0,0 +1,348 @@
+use std::fmt::Debug;
+
+use crate::prelude::*;
+
+// Which sample of which pixel is being taken, out of `count` for that pixel.
+#[derive(Clone, Copy, Debug)]
+pub struct PixelSample {
+    pub pixel: [usize; 2],
+    pub index: usize,
+    pub count: usize,
+}
+
+// Generates the random numbers of a path. Each sample of a pixel is a point in
+// as many dimensions as the path draws numbers, and samplers spread the points
+// of a pixel out over them, dimension by dimension, so that noise falls off
+// faster than with independent numbers. Samplers are pure functions of the
+// sample and dimension, so renders are repeatable.
+pub trait Sampler: Debug {
+    fn get_1d(&self, sample: &PixelSample, dimension: u32) -> float;
+
+    // Two dimensions that are used together, like a position on the pixel.
+    fn get_2d(&self, sample: &PixelSample, dimension: u32) -> [float; 2] {
+        [
+            self.get_1d(sample, dimension),
+            self.get_1d(sample, dimension + 1),
+        ]
+    }
+}
+
+// Uniform random numbers, uncorrelated between samples.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Independent {
+    seed: u64,
+}
+
+impl Independent {
+    #[inline]
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+}
+
+impl Sampler for Independent {
+    fn get_1d(&self, sample: &PixelSample, dimension: u32) -> float {
+        hash_to_norm(sample_hash(sample, dimension, self.seed))
+    }
+}
+
+// Splits every dimension into as many strata as there are samples per pixel,
+// and pairs of dimensions into a grid, giving each sample its own stratum in
+// a random order. Without jitter samples sit at the centers of their strata.
+#[derive(Clone, Copy, Debug)]
+pub struct Stratified {
+    jitter: bool,
+    seed: u64,
+}
+
+impl Default for Stratified {
+    fn default() -> Self {
+        Self {
+            jitter: true,
+            seed: 0,
+        }
+    }
+}
+
+impl Stratified {
+    #[inline]
+    pub fn with_jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    #[inline]
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    fn offset(&self, sample: &PixelSample, dimension: u32) -> float {
+        if self.jitter {
+            hash_to_norm(sample_hash(sample, dimension, self.seed))
+        } else {
+            0.5
+        }
+    }
+}
+
+impl Sampler for Stratified {
+    fn get_1d(&self, sample: &PixelSample, dimension: u32) -> float {
+        let count = sample.count as u32;
+        let hash = pixel_hash(sample, dimension, self.seed);
+        let stratum = permutation_element(sample.index as u32, count, hash as u32);
+        return (stratum as float + self.offset(sample, dimension)) / count as float;
+    }
+
+    fn get_2d(&self, sample: &PixelSample, dimension: u32) -> [float; 2] {
+        // A grid with at least as many cells as samples, as square as possible.
+        let columns = (sample.count as float).sqrt().ceil() as u32;
+        let rows = (sample.count as u32).div_ceil(columns);
+        let hash = pixel_hash(sample, dimension, self.seed);
+        let stratum = permutation_element(sample.index as u32, columns * rows, hash as u32);
+        return [
+            ((stratum % columns) as float + self.offset(sample, dimension)) / columns as float,
+            ((stratum / columns) as float + self.offset(sample, dimension + 1)) / rows as float,
+        ];
+    }
+}
+
+// The Halton sequence, with the radical inverse in a different prime base
+// for every dimension, Owen scrambled per pixel. Dimensions beyond the prime
+// table get independent numbers.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Halton {
+    seed: u64,
+}
+
+impl Halton {
+    #[inline]
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+}
+
+impl Sampler for Halton {
+    fn get_1d(&self, sample: &PixelSample, dimension: u32) -> float {
+        let Some(&base) = PRIMES.get(dimension as usize) else {
+            return hash_to_norm(sample_hash(sample, dimension, self.seed));
+        };
+        let hash = pixel_hash(sample, dimension, self.seed);
+        return owen_scrambled_radical_inverse(base, sample.index as u64, hash as u32);
+    }
+}
+
+// Owen scrambled Sobol points, padded: pairs of dimensions take the first two
+// Sobol dimensions, which are well stratified together, with the samples of
+// the pixel shuffled differently for every pair. Works best with a power of
+// two samples per pixel.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Sobol {
+    seed: u64,
+}
+
+impl Sobol {
+    #[inline]
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+}
+
+impl Sampler for Sobol {
+    fn get_1d(&self, sample: &PixelSample, dimension: u32) -> float {
+        let hash = pixel_hash(sample, dimension, self.seed);
+        let index = permutation_element(sample.index as u32, sample.count as u32, hash as u32);
+        return bits_to_norm(owen_scramble(index.reverse_bits(), (hash >> 32) as u32));
+    }
+
+    fn get_2d(&self, sample: &PixelSample, dimension: u32) -> [float; 2] {
+        let hash = pixel_hash(sample, dimension, self.seed);
+        let index = permutation_element(sample.index as u32, sample.count as u32, hash as u32);
+        let second_hash = mix_bits(hash) as u32;
+        return [
+            bits_to_norm(owen_scramble(index.reverse_bits(), (hash >> 32) as u32)),
+            bits_to_norm(owen_scramble(sobol_second_dimension(index), second_hash)),
+        ];
+    }
+}
+
+// Dimensions drawn by the camera for the pixel position, lens position, time
+// and wavelength, before any bounce.
+const CAMERA_DIMENSIONS: u32 = 6;
+
+// Dimensions set aside for every bounce. A bounce that draws more than this
+// gets independent numbers for the rest.
+const BOUNCE_DIMENSIONS: u32 = 8;
+
+// Marks dimensions past the ones set aside.
+const OVERFLOW_SEED: u64 = 0x6f76_6572_666c_6f77;
+
+// The random numbers of one sample of a pixel, handed down the path so that
+// everything making a random choice draws its own dimensions from the
+// sampler. Each draw says how many dimensions it takes, and pairs that are
+// used together come from `Sampler::get_2d`.
+pub struct SampleContext<'a> {
+    sampler: &'a dyn Sampler,
+    sample: PixelSample,
+    dimension: u32,
+    end: u32,
+}
+
+impl<'a> SampleContext<'a> {
+    // Starts with the camera dimensions.
+    pub fn new(sampler: &'a dyn Sampler, sample: PixelSample) -> Self {
+        Self {
+            sampler,
+            sample,
+            dimension: 0,
+            end: CAMERA_DIMENSIONS,
+        }
+    }
+
+    // Moves on to the dimensions of the given bounce, so that each bounce of
+    // every sample draws from the same dimensions however many the earlier
+    // ones took.
+    pub fn start_bounce(&mut self, bounce: usize) {
+        self.dimension = CAMERA_DIMENSIONS + bounce as u32 * BOUNCE_DIMENSIONS;
+        self.end = self.dimension + BOUNCE_DIMENSIONS;
+    }
+
+    pub fn next_1d(&mut self) -> float {
+        let dimension = self.dimension;
+        self.dimension += 1;
+        if dimension < self.end {
+            return self.sampler.get_1d(&self.sample, dimension);
+        }
+        return self.overflow(dimension);
+    }
+
+    // Two numbers used together, such as a direction.
+    pub fn next_2d(&mut self) -> [float; 2] {
+        let dimension = self.dimension;
+        self.dimension += 2;
+        if dimension + 2 <= self.end {
+            return self.sampler.get_2d(&self.sample, dimension);
+        }
+        return [self.overflow(dimension), self.overflow(dimension + 1)];
+    }
+
+    // Dimensions past the ones set aside get independent numbers that never
+    // repeat the sampler's.
+    fn overflow(&self, dimension: u32) -> float {
+        hash_to_norm(sample_hash(&self.sample, dimension, OVERFLOW_SEED))
+    }
+}
+
+// Hashes the pixel, dimension and seed, for randomizing the samples of one
+// pixel alike.
+fn pixel_hash(sample: &PixelSample, dimension: u32, seed: u64) -> u64 {
+    let pixel = sample.pixel[0] as u64 | (sample.pixel[1] as u64) << 32;
+    mix_bits(mix_bits(pixel ^ seed) ^ dimension as u64)
+}
+
+// Hashes the sample index as well, for numbers independent between samples.
+fn sample_hash(sample: &PixelSample, dimension: u32, seed: u64) -> u64 {
+    mix_bits(pixel_hash(sample, dimension, seed) ^ sample.index as u64)
+}
+
+#[inline]
+fn bits_to_norm(bits: u32) -> float {
+    (bits >> 8) as float / (1u32 << 24) as float
+}
+
+const ONE_MINUS_EPSILON: float = 1.0 - float::EPSILON / 2.0;
+
+// Element `i` of a random permutation of 0..`length` chosen by `seed`, from
+// Kensler, "Correlated Multi-Jittered Sampling", 2013.
+fn permutation_element(mut i: u32, length: u32, seed: u32) -> u32 {
+    let mut w = length.max(1) - 1;
+    w |= w >> 1;
+    w |= w >> 2;
+    w |= w >> 4;
+    w |= w >> 8;
+    w |= w >> 16;
+    loop {
+        i ^= seed;
+        i = i.wrapping_mul(0xe170893d);
+        i ^= seed >> 16;
+        i ^= (i & w) >> 4;
+        i ^= seed >> 8;
+        i = i.wrapping_mul(0x0929eb3f);
+        i ^= seed >> 23;
+        i ^= (i & w) >> 1;
+        i = i.wrapping_mul(1 | seed >> 27);
+        i = i.wrapping_mul(0x6935fa69);
+        i ^= (i & w) >> 11;
+        i = i.wrapping_mul(0x74dcb303);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0x9e501cc3);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0xc860a3df);
+        i &= w;
+        i ^= i >> 5;
+        if i < length {
+            return i.wrapping_add(seed) % length.max(1);
+        }
+    }
+}
+
+// Owen scrambling of the bits of a number in [0, 1) as a hash, from Laine and
+// Karras, "Stratified Sampling for Stochastic Transparency", 2011, with the
+// improved constants used by pbrt.
+fn owen_scramble(bits: u32, seed: u32) -> u32 {
+    let mut v = bits.reverse_bits();
+    v ^= v.wrapping_mul(0x3d20adea);
+    v = v.wrapping_add(seed);
+    v = v.wrapping_mul((seed >> 16) | 1);
+    v ^= v.wrapping_mul(0x05526c56);
+    v ^= v.wrapping_mul(0x53a22864);
+    return v.reverse_bits();
+}
+
+// The second Sobol dimension, whose direction numbers come from the
+// polynomial x + 1.
+fn sobol_second_dimension(index: u32) -> u32 {
+    let mut bits = 0;
+    let mut direction = 1u32 << 31;
+    let mut index = index;
+    while index != 0 {
+        if index & 1 != 0 {
+            bits ^= direction;
+        }
+        direction ^= direction >> 1;
+        index >>= 1;
+    }
+    return bits;
+}
+
+// The digits of `index` in `base` mirrored around the radix point, each digit
+// permuted depending on the ones before it.
+fn owen_scrambled_radical_inverse(base: u32, mut index: u64, seed: u32) -> float {
+    let base = base as u64;
+    let inverse_base = 1.0 / base as f64;
+    let mut inverse_base_power = 1.0;
+    let mut reversed_digits: u64 = 0;
+    while inverse_base_power > (float::EPSILON / 2.0) as f64 {
+        let next = index / base;
+        let digit = index - next * base;
+        let digit_seed = mix_bits(seed as u64 ^ reversed_digits) as u32;
+        let digit = permutation_element(digit as u32, base as u32, digit_seed) as u64;
+        reversed_digits = reversed_digits * base + digit;
+        inverse_base_power *= inverse_base;
+        index = next;
+    }
+    return ((reversed_digits as f64 * inverse_base_power) as float).min(ONE_MINUS_EPSILON);
+}
+
+const PRIMES: [u32; 128] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+    101, 103, 107, 109, 113, 127, 131, 137, 139, 149, 151, 157, 163, 167, 173, 179, 181, 191, 193,
+    197, 199, 211, 223, 227, 229, 233, 239, 241, 251, 257, 263, 269, 271, 277, 281, 283, 293, 307,
+    311, 313, 317, 331, 337, 347, 349, 353, 359, 367, 373, 379, 383, 389, 397, 401, 409, 419, 421,
+    431, 433, 439, 443, 449, 457, 461, 463, 467, 479, 487, 491, 499, 503, 509, 521, 523, 541, 547,
+    557, 563, 569, 571, 577, 587, 593, 599, 601, 607, 613, 617, 619, 631, 641, 643, 647, 653, 659,
+    661, 673, 677, 683, 691, 701, 709, 719,
+];
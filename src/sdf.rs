@@ -0,0 +1,281 @@
+use std::rc::Rc;
+
+use crate::{
+    aabb::Aabb,
+    hittable::{HitRecord, Hittable},
+    interval::Interval,
+    material::Material,
+    prelude::*,
+};
+
+// A signed distance to a surface, negative inside. Operators that stretch
+// space, such as twisting or displacement, may overestimate the distance; use
+// `Sdf::with_step_scale` to march more carefully through those.
+pub trait DistanceField {
+    fn distance(&self, point: &Point3) -> float;
+
+    fn translate(self, offset: Vec3) -> impl DistanceField
+    where
+        Self: Sized,
+    {
+        move |p: &Point3| self.distance(&(p - offset))
+    }
+
+    fn scale(self, factor: float) -> impl DistanceField
+    where
+        Self: Sized,
+    {
+        move |p: &Point3| self.distance(&(p / factor)) * factor
+    }
+
+    // Inflates the surface by `radius`, rounding off its edges.
+    fn round(self, radius: float) -> impl DistanceField
+    where
+        Self: Sized,
+    {
+        move |p: &Point3| self.distance(p) - radius
+    }
+
+    fn union(self, other: impl DistanceField) -> impl DistanceField
+    where
+        Self: Sized,
+    {
+        move |p: &Point3| self.distance(p).min(other.distance(p))
+    }
+
+    fn intersection(self, other: impl DistanceField) -> impl DistanceField
+    where
+        Self: Sized,
+    {
+        move |p: &Point3| self.distance(p).max(other.distance(p))
+    }
+
+    fn subtraction(self, other: impl DistanceField) -> impl DistanceField
+    where
+        Self: Sized,
+    {
+        move |p: &Point3| self.distance(p).max(-other.distance(p))
+    }
+
+    // Blends the surfaces together where they are closer than about
+    // `smoothness`, giving blobby shapes.
+    fn smooth_union(self, other: impl DistanceField, smoothness: float) -> impl DistanceField
+    where
+        Self: Sized,
+    {
+        move |p: &Point3| {
+            let (a, b) = (self.distance(p), other.distance(p));
+            let h = (0.5 + 0.5 * (b - a) / smoothness).clamp(0.0, 1.0);
+            b + (a - b) * h - smoothness * h * (1.0 - h)
+        }
+    }
+
+    // Repeats the shape forever on a grid with the given cell size. Axes with
+    // a period of 0 are not repeated. The shape should fit in one cell.
+    fn repeat(self, period: Vec3) -> impl DistanceField
+    where
+        Self: Sized,
+    {
+        move |p: &Point3| {
+            let mut q = *p;
+            for axis in 0..3 {
+                let size = period.e[axis];
+                if size > 0.0 {
+                    q.e[axis] -= size * (q.e[axis] / size).round();
+                }
+            }
+            self.distance(&q)
+        }
+    }
+
+    // Rotates the XZ plane by `rate` radians per unit along Y.
+    fn twist(self, rate: float) -> impl DistanceField
+    where
+        Self: Sized,
+    {
+        move |p: &Point3| {
+            let (sin, cos) = (rate * p.y()).sin_cos();
+            let q = Point3::new(cos * p.x() + sin * p.z(), p.y(), -sin * p.x() + cos * p.z());
+            self.distance(&q)
+        }
+    }
+
+    // Pushes the surface out by `displacement` at each point.
+    fn displace(self, displacement: impl Fn(&Point3) -> float) -> impl DistanceField
+    where
+        Self: Sized,
+    {
+        move |p: &Point3| self.distance(p) - displacement(p)
+    }
+}
+
+impl<F: Fn(&Point3) -> float> DistanceField for F {
+    fn distance(&self, point: &Point3) -> float {
+        self(point)
+    }
+}
+
+// Primitives are centered at the origin.
+
+pub fn sphere(radius: float) -> impl DistanceField {
+    move |p: &Point3| p.lenght() - radius
+}
+
+pub fn cuboid(half_extents: Vec3) -> impl DistanceField {
+    move |p: &Point3| {
+        let q = p.map(float::abs) - half_extents;
+        let outside = q.map(|c| c.max(0.0)).lenght();
+        let inside = q.x().max(q.y()).max(q.z()).min(0.0);
+        outside + inside
+    }
+}
+
+// A ring around the Y axis.
+pub fn torus(major_radius: float, minor_radius: float) -> impl DistanceField {
+    move |p: &Point3| {
+        let ring = (p.x() * p.x() + p.z() * p.z()).sqrt() - major_radius;
+        (ring * ring + p.y() * p.y()).sqrt() - minor_radius
+    }
+}
+
+pub fn capsule(a: Point3, b: Point3, radius: float) -> impl DistanceField {
+    move |p: &Point3| {
+        let (pa, ba) = (p - a, b - a);
+        let h = (pa.dot(&ba) / ba.lenght_squared()).clamp(0.0, 1.0);
+        (pa - h * ba).lenght() - radius
+    }
+}
+
+// The Mandelbulb fractal with the usual distance estimator; power 8 gives the
+// classic shape. It fits in a sphere of radius about 1.2.
+pub fn mandelbulb(power: float, iterations: usize) -> impl DistanceField {
+    move |p: &Point3| {
+        let mut z = *p;
+        let mut dr = 1.0;
+        let mut r = 0.0;
+        for _ in 0..iterations {
+            r = z.lenght();
+            if r > 2.0 {
+                break;
+            }
+            let theta = (z.z() / r).clamp(-1.0, 1.0).acos() * power;
+            let phi = z.y().atan2(z.x()) * power;
+            dr = r.powf(power - 1.0) * power * dr + 1.0;
+            let zr = r.powf(power);
+            z =
+                zr * Vec3::new(
+                    theta.sin() * phi.cos(),
+                    theta.sin() * phi.sin(),
+                    theta.cos(),
+                ) + p;
+        }
+        if r <= 0.0 {
+            return 0.0;
+        }
+        0.5 * r.ln() * r / dr
+    }
+}
+
+// Renders a distance field inside `bounds` by sphere tracing: marching along
+// the ray by the distance to the closest surface until it is within
+// `epsilon`. Rays may start inside, so refractive materials work too.
+pub struct Sdf {
+    field: Box<dyn DistanceField>,
+    bounds: Aabb,
+    material: Rc<dyn Material>,
+    max_steps: usize,
+    epsilon: float,
+    step_scale: float,
+}
+
+impl Sdf {
+    pub fn new(
+        field: impl DistanceField + 'static,
+        bounds: Aabb,
+        material: Rc<dyn Material>,
+    ) -> Self {
+        Self {
+            field: Box::new(field),
+            bounds,
+            material,
+            max_steps: 256,
+            epsilon: 1e-4,
+            step_scale: 1.0,
+        }
+    }
+
+    #[inline]
+    pub fn with_max_steps(mut self, max_steps: usize) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+
+    #[inline]
+    pub fn with_epsilon(mut self, epsilon: float) -> Self {
+        self.epsilon = epsilon;
+        self
+    }
+
+    // Scales every step, below 1 for fields that overestimate distances.
+    #[inline]
+    pub fn with_step_scale(mut self, step_scale: float) -> Self {
+        self.step_scale = step_scale;
+        self
+    }
+
+    // The gradient by central differences, which points out of the surface.
+    fn normal(&self, point: &Point3) -> Vec3 {
+        let h = self.epsilon.max(1e-5);
+        let gradient = Vec3::from_array(std::array::from_fn(|axis| {
+            let mut offset = Vec3::ZERO;
+            offset.e[axis] = h;
+            self.field.distance(&(point + offset)) - self.field.distance(&(point - offset))
+        }));
+        return gradient.unit();
+    }
+}
+
+impl Hittable for Sdf {
+    fn hit(&self, ray: &Ray, t_interval: Interval) -> Option<HitRecord> {
+        let span = self.bounds.hit(ray, t_interval)?;
+        let speed = ray.direction().lenght();
+
+        // Rays leaving a surface start within epsilon of it, so only accept
+        // hits once the ray has been further away than that. Rays entering the
+        // bounds from outside are not leaving a surface, and may well graze
+        // one right where they enter.
+        let mut armed = span.min > t_interval.min;
+        let mut t = span.min;
+        for _ in 0..self.max_steps {
+            if t > span.max {
+                return None;
+            }
+            let distance = self.field.distance(&ray.at(t)).abs();
+            if distance < self.epsilon {
+                if armed {
+                    let position = ray.at(t);
+                    let outward_normal = self.normal(&position);
+                    let frame = Frame::from_normal(&outward_normal);
+                    return Some(
+                        HitRecord::from_outward_normal(
+                            t,
+                            position,
+                            self.material.clone(),
+                            outward_normal,
+                            ray,
+                        )
+                        .with_tangents(frame.x, frame.y),
+                    );
+                }
+            } else {
+                armed = true;
+            }
+            t += self.step_scale * distance.max(self.epsilon) / speed;
+        }
+        return None;
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bounds
+    }
+}
@@ -0,0 +1,201 @@
+use std::sync::OnceLock;
+
+use crate::prelude::*;
+
+// Wavelengths are in nanometers.
+pub const LAMBDA_MIN: float = 360.0;
+pub const LAMBDA_MAX: float = 830.0;
+
+// The wavelength used for dispersive indices of refraction outside spectral
+// mode (the helium d-line).
+pub const LAMBDA_D: float = 587.6;
+
+// Maps a uniform number to a wavelength sampled in proportion to the visual
+// response, as in pbrt-v4.
+pub fn sample_wavelength(u: float) -> float {
+    538.0 - 138.888_89 * (0.856_910_6 - 1.827_502 * u).atanh()
+}
+
+pub fn wavelength_pdf(lambda: float) -> float {
+    if !(LAMBDA_MIN..=LAMBDA_MAX).contains(&lambda) {
+        return 0.0;
+    }
+    0.003_939_804 / (0.0072 * (lambda - 538.0)).cosh().powi(2)
+}
+
+// CIE 1931 color matching functions using the multi-lobe Gaussian fit from
+// Wyman et al., "Simple Analytic Approximations to the CIE XYZ Color Matching
+// Functions", 2013.
+pub fn cie_xyz(lambda: float) -> Vec3 {
+    let g = |mu: float, sigma_low: float, sigma_high: float| {
+        let t = (lambda - mu) / if lambda < mu { sigma_low } else { sigma_high };
+        (-0.5 * t * t).exp()
+    };
+    Vec3::new(
+        1.056 * g(599.8, 37.9, 31.0) + 0.362 * g(442.0, 16.0, 26.7) - 0.065 * g(501.1, 20.4, 26.2),
+        0.821 * g(568.8, 46.9, 40.5) + 0.286 * g(530.9, 16.3, 31.1),
+        1.217 * g(437.0, 11.8, 36.0) + 0.681 * g(459.0, 26.0, 13.8),
+    )
+}
+
+pub fn xyz_to_linear_srgb(xyz: &Vec3) -> Color {
+    const XYZ_TO_SRGB: Mat3 = Mat3::from_rows([
+        [3.2406, -1.5372, -0.4986],
+        [-0.9689, 1.8758, 0.0415],
+        [0.0557, -0.2040, 1.0570],
+    ]);
+    XYZ_TO_SRGB * xyz
+}
+
+// Converts a single wavelength radiance sample to an RGB estimate. A constant
+// spectrum of 1 maps to white (1, 1, 1) on average.
+pub fn spectral_sample_to_rgb(value: float, lambda: float) -> Color {
+    let pdf = wavelength_pdf(lambda);
+    if pdf <= 0.0 {
+        return Color::ZERO;
+    }
+    xyz_to_linear_srgb(&(cie_xyz(lambda) * (value / pdf))) / white_balance()
+}
+
+// Integrates a constant unit spectrum to RGB, so that the equal energy white
+// point can be mapped to sRGB white.
+fn white_balance() -> Color {
+    static WHITE: OnceLock<[float; 3]> = OnceLock::new();
+    let white = WHITE.get_or_init(|| {
+        let steps = (LAMBDA_MAX - LAMBDA_MIN) as usize;
+        let xyz = (0..steps)
+            .map(|i| cie_xyz(LAMBDA_MIN + i as float + 0.5))
+            .sum::<Vec3>();
+        xyz_to_linear_srgb(&xyz).e
+    });
+    Color::from_array(*white)
+}
+
+// Projects a spectrum given by `f` to RGB using a fixed set of wavelengths,
+// for spectrally varying effects evaluated outside spectral mode. A constant
+// spectrum of 1 maps to white.
+pub fn spectrum_to_rgb(f: impl Fn(float) -> float) -> Color {
+    const STEPS: usize = 24;
+    let step = (720.0 - 380.0) / STEPS as float;
+    let (color, white) = (0..STEPS)
+        .map(|i| 380.0 + (i as float + 0.5) * step)
+        .map(|lambda| (lambda, xyz_to_linear_srgb(&cie_xyz(lambda))))
+        .fold(
+            (Color::ZERO, Color::ZERO),
+            |(color, white), (lambda, rgb)| (color + f(lambda) * rgb, white + rgb),
+        );
+    return color / white;
+}
+
+// The color of a blackbody at `temperature` Kelvin, with its spectrum scaled
+// to 1 at the peak wavelength. Bodies that peak in the infrared are dim and
+// red, and out of gamut colors are clipped to 0.
+pub fn blackbody(temperature: float) -> Color {
+    if temperature <= 0.0 {
+        return Color::ZERO;
+    }
+    // Second radiation constant hc/k in nm K, and Wien's displacement
+    // constant in nm K.
+    const C2: float = 1.438_777e7;
+    let peak = 2.897_772e6 / temperature;
+    let planck = |lambda: float| (C2 / (lambda * temperature)).exp_m1().recip() / lambda.powi(5);
+    let normalization = planck(peak);
+    return spectrum_to_rgb(|lambda| planck(lambda) / normalization).map(|c| c.max(0.0));
+}
+
+// Smooth reflectance spectra for Smits' RGB to spectrum conversion ("An RGB
+// to Spectrum Conversion for Reflectances", 1999), built from logistic steps
+// instead of tabulated bins. White is exactly flat and all bases stay in
+// [0, 1], so reflectances stay physically plausible.
+fn red_basis(lambda: float) -> float {
+    1.0 / (1.0 + (-(lambda - 590.0) / 12.0).exp())
+}
+
+fn blue_basis(lambda: float) -> float {
+    1.0 - 1.0 / (1.0 + (-(lambda - 495.0) / 12.0).exp())
+}
+
+fn green_basis(lambda: float) -> float {
+    (1.0 - red_basis(lambda) - blue_basis(lambda)).max(0.0)
+}
+
+// Evaluates an upsampled spectrum for `rgb` at `lambda`.
+pub fn rgb_to_spectrum(rgb: &Color, lambda: float) -> float {
+    let (r, g, b) = (rgb.r(), rgb.g(), rgb.b());
+    let (red, green, blue) = (red_basis(lambda), green_basis(lambda), blue_basis(lambda));
+    let (cyan, magenta, yellow) = (1.0 - red, 1.0 - green, 1.0 - blue);
+
+    // Take out the white part, then the secondary color shared by the two
+    // largest channels, then what's left of the largest one.
+    return if r <= g && r <= b {
+        r + if g <= b {
+            (g - r) * cyan + (b - g) * blue
+        } else {
+            (b - r) * cyan + (g - b) * green
+        }
+    } else if g <= r && g <= b {
+        g + if r <= b {
+            (r - g) * magenta + (b - r) * blue
+        } else {
+            (b - g) * magenta + (r - b) * red
+        }
+    } else {
+        b + if r <= g {
+            (r - b) * yellow + (g - r) * green
+        } else {
+            (g - b) * yellow + (r - g) * red
+        }
+    };
+}
+
+// A wavelength dependent index of refraction.
+#[derive(Clone, Copy, Debug)]
+pub enum Ior {
+    Constant(float),
+    // n = a + b / lambda^2, with lambda in micrometers.
+    Cauchy { a: float, b: float },
+    // n^2 = 1 + sum(b_i lambda^2 / (lambda^2 - c_i)), with lambda in
+    // micrometers.
+    Sellmeier { b: [float; 3], c: [float; 3] },
+}
+
+impl Ior {
+    pub const BK7: Ior = Ior::Sellmeier {
+        b: [1.039_612, 0.231_792_34, 1.010_469_5],
+        c: [0.006_000_699, 0.020_017_914, 103.560_65],
+    };
+    pub const FUSED_SILICA: Ior = Ior::Sellmeier {
+        b: [0.696_166_3, 0.407_942_6, 0.897_479_4],
+        c: [0.004_679_148, 0.013_512_063, 97.934],
+    };
+    pub const DIAMOND: Ior = Ior::Sellmeier {
+        b: [0.3306, 4.3356, 0.0],
+        c: [0.030_625, 0.011_236, 0.0],
+    };
+    // Dense flint glass, for strongly dispersive prisms.
+    pub const SF11: Ior = Ior::Sellmeier {
+        b: [1.737_596_9, 0.313_747_35, 1.898_781],
+        c: [0.013_188_707, 0.062_306_814, 155.236_3],
+    };
+
+    // Evaluates the index at `wavelength`, or at the d-line for rays that
+    // don't carry a wavelength.
+    pub fn at(&self, wavelength: Option<float>) -> float {
+        let micrometers = wavelength.unwrap_or(LAMBDA_D) / 1000.0;
+        let lambda2 = micrometers * micrometers;
+        match self {
+            Ior::Constant(n) => *n,
+            Ior::Cauchy { a, b } => a + b / lambda2,
+            Ior::Sellmeier { b, c } => {
+                let sum: float = (0..3).map(|i| b[i] * lambda2 / (lambda2 - c[i])).sum();
+                (1.0 + sum).sqrt()
+            }
+        }
+    }
+}
+
+impl From<float> for Ior {
+    fn from(value: float) -> Self {
+        Ior::Constant(value)
+    }
+}
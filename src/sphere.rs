@@ -1,6 +1,8 @@
 use std::rc::Rc;
 
 use crate::{
+    aabb::Aabb,
+    csg::{Solid, Span},
     hittable::{HitRecord, Hittable},
     interval::Interval,
     material::Material,
@@ -8,7 +10,7 @@ use crate::{
 };
 
 pub struct Sphere {
-    center: Point3,
+    center: Ray,
     radius: float,
     material: Rc<dyn Material>,
 }
@@ -16,36 +18,108 @@ pub struct Sphere {
 impl Sphere {
     pub fn new(center: Point3, radius: float, material: Rc<dyn Material>) -> Self {
         Self {
-            center,
+            center: Ray::new(center, Vec3::ZERO),
             radius,
             material,
         }
     }
-}
 
-impl Hittable for Sphere {
-    fn hit(&self, ray: &Ray, t_interval: Interval) -> Option<HitRecord> {
-        let center_to_origin = ray.origin() - self.center;
+    // A sphere whose center moves linearly from `center_0` at time 0 to
+    // `center_1` at time 1. Outside that range it stays at the nearest end.
+    pub fn new_moving(
+        center_0: Point3,
+        center_1: Point3,
+        radius: float,
+        material: Rc<dyn Material>,
+    ) -> Self {
+        Self {
+            center: Ray::new(center_0, center_1 - center_0),
+            radius,
+            material,
+        }
+    }
+
+    #[inline]
+    fn center(&self, time: float) -> Point3 {
+        self.center.at(time.clamp(0.0, 1.0))
+    }
+
+    // Maps a point on the unit sphere to (u, v), with u following the
+    // longitude from -X around through +Z and v the latitude from -Y to +Y.
+    #[inline]
+    fn uv(point: &Point3) -> (float, float) {
+        let theta = (-point.y()).clamp(-1.0, 1.0).acos();
+        let phi = (-point.z()).atan2(point.x()) + PI;
+        (phi / (2.0 * PI), theta / PI)
+    }
+
+    // Partial derivatives of the surface position along u and v at a point on
+    // the unit sphere, matching the parameterization of `uv`.
+    #[inline]
+    fn tangents(&self, point: &Point3) -> (Vec3, Vec3) {
+        let (x, y, z) = (point.x(), point.y(), point.z());
+        let ring_radius = (x * x + z * z).sqrt().max(1e-6);
+        let dpdu = 2.0 * PI * self.radius * Vec3::new(z, 0.0, -x);
+        let dpdv =
+            PI * self.radius * Vec3::new(-x * y / ring_radius, ring_radius, -y * z / ring_radius);
+        (dpdu, dpdv)
+    }
+
+    // Solves for the ray parameters where it crosses the sphere, in order.
+    fn roots(&self, ray: &Ray) -> Option<(float, float)> {
+        let center_to_origin = ray.origin() - self.center(ray.time());
         let a = ray.direction().lenght_squared();
         let half_b = center_to_origin.dot(&ray.direction());
         let c = center_to_origin.lenght_squared() - self.radius * self.radius;
 
         let delta = half_b * half_b - a * c;
         let delta_sqrt = (delta >= 0.0).then(|| delta.sqrt())?;
+        Some(((-half_b - delta_sqrt) / a, (-half_b + delta_sqrt) / a))
+    }
 
-        let t = t_interval
-            .surrounds_some((-half_b - delta_sqrt) / a)
-            .or_else(|| t_interval.surrounds_some((-half_b + delta_sqrt) / a))?;
-
+    fn record(&self, ray: &Ray, t: float) -> HitRecord {
         let position = ray.at(t);
-        let outward_normal = (position - self.center) / self.radius;
+        let outward_normal = (position - self.center(ray.time())) / self.radius;
+        let (u, v) = Self::uv(&outward_normal);
+        let (dpdu, dpdv) = self.tangents(&outward_normal);
 
-        return Some(HitRecord::from_outward_normal(
+        return HitRecord::from_outward_normal(
             t,
             position,
             self.material.clone(),
             outward_normal,
-            &ray,
-        ));
+            ray,
+        )
+        .with_uv(u, v)
+        .with_tangents(dpdu, dpdv);
+    }
+}
+
+impl Hittable for Sphere {
+    fn hit(&self, ray: &Ray, t_interval: Interval) -> Option<HitRecord> {
+        let (t0, t1) = self.roots(ray)?;
+        let t = t_interval
+            .surrounds_some(t0)
+            .or_else(|| t_interval.surrounds_some(t1))?;
+        return Some(self.record(ray, t));
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let radius = Vec3::uniform(self.radius);
+        let (center_0, center_1) = (self.center(0.0), self.center(1.0));
+        Aabb::from_points(center_0 - radius, center_0 + radius)
+            .union(&Aabb::from_points(center_1 - radius, center_1 + radius))
+    }
+}
+
+impl Solid for Sphere {
+    fn spans(&self, ray: &Ray) -> Vec<Span> {
+        match self.roots(ray) {
+            Some((t0, t1)) if t0 < t1 => vec![Span {
+                enter: self.record(ray, t0),
+                exit: self.record(ray, t1),
+            }],
+            _ => vec![],
+        }
     }
 }
@@ -0,0 +1,131 @@
+use crate::{
+    hittable::HitRecord,
+    material::Material,
+    medium::sample_henyey_greenstein,
+    microfacet::{fresnel_dielectric, refract},
+    prelude::*,
+    sampler::SampleContext,
+    spectrum::rgb_to_spectrum,
+};
+
+// Random walk subsurface scattering inside a closed object with a smooth
+// dielectric boundary. Light refracts in at front faces, and every back face
+// hit ends a free flight inside, which either scatters at a sampled distance
+// before the boundary or reaches it and refracts out. The walk takes one
+// bounce per step, so dense media want a high camera max depth.
+//
+// `albedo` is the single scattering albedo and `mean_free_path` the average
+// distance between scattering events, both per channel. Sampling distances
+// for three channels at once gets noisy for very different mean free paths;
+// in spectral mode each walk has a single wavelength and avoids this.
+#[derive(Debug)]
+pub struct Subsurface {
+    albedo: Color,
+    extinction: Color,
+    index_of_refraction: float,
+    g: float,
+}
+
+impl Subsurface {
+    pub fn new(albedo: Color, mean_free_path: Color) -> Self {
+        Self {
+            albedo,
+            extinction: mean_free_path.map(|d| 1.0 / d.max(1e-6)),
+            index_of_refraction: 1.4,
+            g: 0.0,
+        }
+    }
+
+    // Picks the single scattering albedo that makes a thick slab look like
+    // `surface_albedo` after multiple scattering, using the fit from Chiang et
+    // al., "Practical and Controllable Subsurface Scattering for Production
+    // Path Tracing", 2016.
+    pub fn from_surface_albedo(surface_albedo: Color, mean_free_path: Color) -> Self {
+        let albedo = surface_albedo.map(|a| {
+            let a = a.clamp(0.0, 0.999);
+            let s = 4.09712 + 4.20863 * a - (9.59217 + 41.6808 * a + 17.7126 * a * a).sqrt();
+            1.0 - s * s
+        });
+        Self::new(albedo, mean_free_path)
+    }
+
+    #[inline]
+    pub fn with_index_of_refraction(mut self, index_of_refraction: float) -> Self {
+        self.index_of_refraction = index_of_refraction;
+        self
+    }
+
+    // Henyey-Greenstein asymmetry of the scattering inside.
+    #[inline]
+    pub fn with_anisotropy(mut self, g: float) -> Self {
+        self.g = g.clamp(-0.99, 0.99);
+        self
+    }
+
+    // Smooth dielectric interface, choosing between reflection and refraction
+    // by their Fresnel probabilities.
+    fn cross_boundary(
+        &self,
+        in_ray: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut SampleContext,
+    ) -> Ray {
+        let eta = if hit_record.front_face {
+            self.index_of_refraction
+        } else {
+            1.0 / self.index_of_refraction
+        };
+        let wo = -in_ray.direction().unit();
+        let normal = hit_record.normal;
+        let reflectance = fresnel_dielectric(wo.dot(&normal), eta);
+        let direction = refract(&wo, &normal, eta)
+            .filter(|_| sampler.next_1d() >= reflectance)
+            .unwrap_or_else(|| (-wo).reflect(&normal));
+        return in_ray.spawn(hit_record.position, direction);
+    }
+}
+
+impl Material for Subsurface {
+    fn scatter(
+        &self,
+        in_ray: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut SampleContext,
+    ) -> Option<(Color, Ray)> {
+        if hit_record.front_face {
+            return Some((
+                Color::uniform(1.0),
+                self.cross_boundary(in_ray, hit_record, sampler),
+            ));
+        }
+
+        // The ray travelled inside since the last event. Sample the flight
+        // distance from a random channel's extinction, and weight by the
+        // ratio to the average over all channels of the sampling pdf.
+        let (albedo, extinction) = match in_ray.wavelength() {
+            Some(lambda) => (
+                Color::uniform(rgb_to_spectrum(&self.albedo, lambda)),
+                Color::uniform(rgb_to_spectrum(&self.extinction, lambda)),
+            ),
+            None => (self.albedo, self.extinction),
+        };
+        let speed = in_ray.direction().lenght();
+        let distance = hit_record.t * speed;
+        let channel = ((3.0 * sampler.next_1d()) as usize).min(2);
+        let flight = -(1.0 - sampler.next_1d()).ln() / extinction.e[channel];
+        let average = |c: Color| (c.r() + c.g() + c.b()) / 3.0;
+
+        if flight < distance {
+            let transmittance = (-extinction * flight).map(float::exp);
+            let pdf = average(extinction * transmittance);
+            let weight = albedo * extinction * transmittance / pdf;
+            let position = in_ray.at(flight / speed);
+            let direction = sample_henyey_greenstein(&in_ray.direction(), self.g, sampler);
+            return Some((weight, in_ray.spawn(position, direction)));
+        }
+
+        let transmittance = (-extinction * distance).map(float::exp);
+        let weight = transmittance / average(transmittance);
+        return Some((weight, self.cross_boundary(in_ray, hit_record, sampler)));
+    }
+}
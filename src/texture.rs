@@ -0,0 +1,203 @@
+use std::{fmt::Debug, path::Path, rc::Rc};
+
+use crate::{
+    image::{Image, ImageResult},
+    prelude::*,
+};
+
+pub trait Texture: Debug {
+    fn value(&self, u: float, v: float, position: &Point3) -> Color;
+
+    // For textures driving scalar parameters such as roughness.
+    fn scalar_value(&self, u: float, v: float, position: &Point3) -> float {
+        let color = self.value(u, v, position);
+        (color.r() + color.g() + color.b()) / 3.0
+    }
+}
+
+#[derive(Debug)]
+pub struct SolidColor {
+    albedo: Color,
+}
+
+impl SolidColor {
+    pub fn new(albedo: Color) -> Self {
+        Self { albedo }
+    }
+}
+
+impl Texture for SolidColor {
+    fn value(&self, _u: float, _v: float, _position: &Point3) -> Color {
+        self.albedo
+    }
+}
+
+// Alternates between two textures in a 3D grid of cubes with side `scale`.
+#[derive(Debug)]
+pub struct Checker {
+    inverse_scale: float,
+    even: Rc<dyn Texture>,
+    odd: Rc<dyn Texture>,
+}
+
+impl Checker {
+    pub fn new(scale: float, even: Rc<dyn Texture>, odd: Rc<dyn Texture>) -> Self {
+        Self {
+            inverse_scale: 1.0 / scale,
+            even,
+            odd,
+        }
+    }
+
+    pub fn from_colors(scale: float, even: Color, odd: Color) -> Self {
+        Self::new(
+            scale,
+            Rc::new(SolidColor::new(even)),
+            Rc::new(SolidColor::new(odd)),
+        )
+    }
+}
+
+impl Texture for Checker {
+    fn value(&self, u: float, v: float, position: &Point3) -> Color {
+        let cell = position.map(|c| (c * self.inverse_scale).floor());
+        let sum = (cell.x() + cell.y() + cell.z()) as i64;
+        if sum.rem_euclid(2) == 0 {
+            self.even.value(u, v, position)
+        } else {
+            self.odd.value(u, v, position)
+        }
+    }
+}
+
+// Alternates between two textures in a grid of `u_count` by `v_count` squares
+// over the surface's UV parameterization.
+#[derive(Debug)]
+pub struct UvChecker {
+    u_count: float,
+    v_count: float,
+    even: Rc<dyn Texture>,
+    odd: Rc<dyn Texture>,
+}
+
+impl UvChecker {
+    pub fn new(
+        u_count: float,
+        v_count: float,
+        even: Rc<dyn Texture>,
+        odd: Rc<dyn Texture>,
+    ) -> Self {
+        Self {
+            u_count,
+            v_count,
+            even,
+            odd,
+        }
+    }
+
+    pub fn from_colors(u_count: float, v_count: float, even: Color, odd: Color) -> Self {
+        Self::new(
+            u_count,
+            v_count,
+            Rc::new(SolidColor::new(even)),
+            Rc::new(SolidColor::new(odd)),
+        )
+    }
+}
+
+impl Texture for UvChecker {
+    fn value(&self, u: float, v: float, position: &Point3) -> Color {
+        let sum = (u * self.u_count).floor() as i64 + (v * self.v_count).floor() as i64;
+        if sum.rem_euclid(2) == 0 {
+            self.even.value(u, v, position)
+        } else {
+            self.odd.value(u, v, position)
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub enum WrapMode {
+    #[default]
+    Repeat,
+    Clamp,
+    Mirror,
+}
+
+impl WrapMode {
+    #[inline]
+    fn apply(&self, index: i64, size: usize) -> usize {
+        let size = size as i64;
+        let index = match self {
+            WrapMode::Repeat => index.rem_euclid(size),
+            WrapMode::Clamp => index.clamp(0, size - 1),
+            WrapMode::Mirror => {
+                let index = index.rem_euclid(2 * size);
+                if index >= size {
+                    2 * size - 1 - index
+                } else {
+                    index
+                }
+            }
+        };
+        index as usize
+    }
+}
+
+// Samples an image with bilinear filtering. `v` runs from the bottom row (0)
+// to the top row (1) of the image.
+#[derive(Debug)]
+pub struct ImageTexture {
+    image: Rc<Image>,
+    wrap: WrapMode,
+}
+
+impl ImageTexture {
+    // Lookups wrap or clamp into the image, which needs a pixel to land on.
+    pub fn new(image: Rc<Image>) -> Self {
+        assert!(
+            image.width() > 0 && image.height() > 0,
+            "image texture must not be empty"
+        );
+        Self {
+            image,
+            wrap: WrapMode::default(),
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> ImageResult<Self> {
+        Ok(Self::new(Rc::new(Image::load(path)?)))
+    }
+
+    pub fn load_raw(path: impl AsRef<Path>) -> ImageResult<Self> {
+        Ok(Self::new(Rc::new(Image::load_raw(path)?)))
+    }
+
+    #[inline]
+    pub fn with_wrap(mut self, wrap: WrapMode) -> Self {
+        self.wrap = wrap;
+        self
+    }
+
+    #[inline]
+    fn texel(&self, x: i64, y: i64) -> Color {
+        self.image.pixel(
+            self.wrap.apply(x, self.image.width()),
+            self.wrap.apply(y, self.image.height()),
+        )
+    }
+}
+
+impl Texture for ImageTexture {
+    fn value(&self, u: float, v: float, _position: &Point3) -> Color {
+        let x = u * self.image.width() as float - 0.5;
+        let y = (1.0 - v) * self.image.height() as float - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as i64, y0 as i64);
+
+        let top = (1.0 - fx) * self.texel(x0, y0) + fx * self.texel(x0 + 1, y0);
+        let bottom = (1.0 - fx) * self.texel(x0, y0 + 1) + fx * self.texel(x0 + 1, y0 + 1);
+        return (1.0 - fy) * top + fy * bottom;
+    }
+}
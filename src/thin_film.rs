@@ -0,0 +1,166 @@
+use std::{
+    ops::{Add, Div, Mul, Sub},
+    rc::Rc,
+};
+
+use crate::{
+    hittable::HitRecord,
+    prelude::*,
+    spectrum::spectrum_to_rgb,
+    texture::{SolidColor, Texture},
+};
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Complex {
+    pub re: float,
+    pub im: float,
+}
+
+impl Complex {
+    pub const fn new(re: float, im: float) -> Self {
+        Self { re, im }
+    }
+
+    pub const fn real(re: float) -> Self {
+        Self::new(re, 0.0)
+    }
+
+    pub fn norm_squared(&self) -> float {
+        self.re * self.re + self.im * self.im
+    }
+
+    // The principal square root.
+    pub fn sqrt(&self) -> Self {
+        let norm = self.norm_squared().sqrt();
+        let re = (0.5 * (norm + self.re)).max(0.0).sqrt();
+        let im = (0.5 * (norm - self.re)).max(0.0).sqrt();
+        Self::new(re, if self.im < 0.0 { -im } else { im })
+    }
+
+    pub fn exp(&self) -> Self {
+        let scale = self.re.exp();
+        Self::new(scale * self.im.cos(), scale * self.im.sin())
+    }
+}
+
+impl Add for Complex {
+    type Output = Complex;
+
+    fn add(self, rhs: Complex) -> Complex {
+        Complex::new(self.re + rhs.re, self.im + rhs.im)
+    }
+}
+
+impl Sub for Complex {
+    type Output = Complex;
+
+    fn sub(self, rhs: Complex) -> Complex {
+        Complex::new(self.re - rhs.re, self.im - rhs.im)
+    }
+}
+
+impl Mul for Complex {
+    type Output = Complex;
+
+    fn mul(self, rhs: Complex) -> Complex {
+        Complex::new(
+            self.re * rhs.re - self.im * rhs.im,
+            self.re * rhs.im + self.im * rhs.re,
+        )
+    }
+}
+
+impl Div for Complex {
+    type Output = Complex;
+
+    fn div(self, rhs: Complex) -> Complex {
+        let denominator = rhs.norm_squared();
+        Complex::new(
+            (self.re * rhs.re + self.im * rhs.im) / denominator,
+            (self.im * rhs.re - self.re * rhs.im) / denominator,
+        )
+    }
+}
+
+// A thin coating on top of a surface, whose reflections interfere with the
+// ones from the surface below. Thickness is in nanometers.
+#[derive(Debug)]
+pub struct ThinFilm {
+    thickness: Rc<dyn Texture>,
+    index_of_refraction: float,
+}
+
+impl ThinFilm {
+    pub fn new(thickness: float, index_of_refraction: float) -> Self {
+        Self::from_texture(
+            Rc::new(SolidColor::new(Color::uniform(thickness))),
+            index_of_refraction,
+        )
+    }
+
+    // Varying the thickness over the surface gives the swirls of soap bubbles
+    // and oil slicks.
+    pub fn from_texture(thickness: Rc<dyn Texture>, index_of_refraction: float) -> Self {
+        Self {
+            thickness,
+            index_of_refraction,
+        }
+    }
+
+    // Unpolarized reflectance of the layered interface at `cos_theta` from
+    // the incident side. `indices` gives the incident and substrate indices
+    // for a wavelength. Outside spectral mode the interference is resolved
+    // over the visible spectrum and projected to RGB.
+    pub fn reflectance(
+        &self,
+        hit_record: &HitRecord,
+        cos_theta: float,
+        wavelength: Option<float>,
+        indices: impl Fn(float) -> (Complex, Complex),
+    ) -> Color {
+        let thickness =
+            self.thickness
+                .scalar_value(hit_record.u, hit_record.v, &hit_record.position);
+        let film = Complex::real(self.index_of_refraction);
+        let reflectance = |lambda: float| {
+            let (incident, substrate) = indices(lambda);
+            airy_reflectance(cos_theta, incident, film, substrate, thickness, lambda)
+        };
+        return match wavelength {
+            Some(lambda) => Color::uniform(reflectance(lambda)),
+            None => spectrum_to_rgb(reflectance),
+        };
+    }
+}
+
+// Sums the multiple reflections inside a film of index `n2` between media
+// `n1` and `n3`, averaging the s and p polarizations.
+fn airy_reflectance(
+    cos_theta: float,
+    n1: Complex,
+    n2: Complex,
+    n3: Complex,
+    thickness: float,
+    lambda: float,
+) -> float {
+    let one = Complex::real(1.0);
+    let sin2 = Complex::real(1.0 - cos_theta * cos_theta);
+    let cosine = |n: Complex| (one - sin2 * (n1 / n) * (n1 / n)).sqrt();
+    let (cos1, cos2, cos3) = (Complex::real(cos_theta), cosine(n2), cosine(n3));
+
+    let s = |na: Complex, ca: Complex, nb: Complex, cb: Complex| {
+        (na * ca - nb * cb) / (na * ca + nb * cb)
+    };
+    let p = |na: Complex, ca: Complex, nb: Complex, cb: Complex| {
+        (nb * ca - na * cb) / (nb * ca + na * cb)
+    };
+
+    let phase = Complex::new(0.0, 4.0 * PI * thickness / lambda) * n2 * cos2;
+    let phase = phase.exp();
+    let airy = |r12: Complex, r23: Complex| {
+        ((r12 + r23 * phase) / (one + r12 * r23 * phase)).norm_squared()
+    };
+    let rs = airy(s(n1, cos1, n2, cos2), s(n2, cos2, n3, cos3));
+    let rp = airy(p(n1, cos1, n2, cos2), p(n2, cos2, n3, cos3));
+    return (0.5 * (rs + rp)).clamp(0.0, 1.0);
+}
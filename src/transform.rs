@@ -0,0 +1,266 @@
+use crate::{
+    aabb::Aabb,
+    csg::{Solid, Span},
+    hittable::{HitRecord, Hittable},
+    interval::Interval,
+    prelude::*,
+    sampler::SampleContext,
+};
+
+#[derive(Clone, Copy, Debug)]
+pub struct Keyframe {
+    pub translation: Vec3,
+    pub rotation: Quat,
+    pub scale: Vec3,
+}
+
+impl Default for Keyframe {
+    fn default() -> Self {
+        Self::new(Vec3::ZERO, Quat::IDENTITY, Vec3::uniform(1.0))
+    }
+}
+
+impl Keyframe {
+    #[inline]
+    pub const fn new(translation: Vec3, rotation: Quat, scale: Vec3) -> Self {
+        Self {
+            translation,
+            rotation,
+            scale,
+        }
+    }
+
+    #[inline]
+    pub fn lerp(&self, other: &Keyframe, t: float) -> Self {
+        Self::new(
+            (1.0 - t) * self.translation + t * other.translation,
+            self.rotation.slerp(&other.rotation, t),
+            (1.0 - t) * self.scale + t * other.scale,
+        )
+    }
+
+    #[inline]
+    pub fn to_world(&self) -> Mat4 {
+        Mat4::from_linear_translation(
+            self.rotation.to_mat3() * Mat3::scale(self.scale),
+            self.translation,
+        )
+    }
+
+    #[inline]
+    pub fn to_object(&self) -> Mat4 {
+        let linear = Mat3::scale(self.scale.map(|s| 1.0 / s)) * self.rotation.conjugate().to_mat3();
+        Mat4::from_linear_translation(linear, linear.transform(&-self.translation))
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Motion {
+    Static { to_world: Mat4, to_object: Mat4 },
+    Animated { start: Keyframe, end: Keyframe },
+}
+
+// An object placed in the world by a transform. Transformed solids are solids
+// too, e.g. for carving with a rotated box.
+pub struct Transformed<T: ?Sized = dyn Hittable> {
+    object: Box<T>,
+    motion: Motion,
+    bounding_box: Aabb,
+}
+
+impl<T: Hittable + ?Sized> Transformed<T> {
+    // None if `to_world` is singular, such as a scale of zero along an axis.
+    pub fn new(object: Box<T>, to_world: Mat4) -> Option<Self> {
+        let to_object = to_world.inverse()?;
+        let motion = Motion::Static {
+            to_world,
+            to_object,
+        };
+        return Some(Self::from_motion(object, motion));
+    }
+
+    // Interpolates between `start` at time 0 and `end` at time 1, slerping the
+    // rotation. Outside that range the object holds the nearest keyframe. None
+    // if a scale is zero along an axis, or changes sign between the keyframes
+    // and so passes through zero.
+    pub fn new_animated(object: Box<T>, start: Keyframe, end: Keyframe) -> Option<Self> {
+        let invertible = (0..3).all(|axis| {
+            let product = start.scale.e[axis] * end.scale.e[axis];
+            product > 0.0 && product.is_finite()
+        });
+        if !invertible {
+            return None;
+        }
+        return Some(Self::from_motion(object, Motion::Animated { start, end }));
+    }
+
+    fn from_motion(object: Box<T>, motion: Motion) -> Self {
+        let mut transformed = Self {
+            object,
+            motion,
+            bounding_box: Aabb::EMPTY,
+        };
+        transformed.bounding_box = transformed.motion_bounds();
+        transformed
+    }
+
+    // Runs `query` on the ray in object space, with a function bringing the
+    // records it finds back to world space. Ray parameters are the same in
+    // both.
+    fn in_object_space<R>(
+        &self,
+        ray: &Ray,
+        query: impl FnOnce(&Ray, &dyn Fn(HitRecord) -> HitRecord) -> R,
+    ) -> R {
+        let (to_world, to_object) = self.matrices(ray.time());
+        let object_ray = ray.spawn(
+            to_object.transform_point(&ray.origin()),
+            to_object.transform_vector(&ray.direction()),
+        );
+
+        let normal_matrix = to_object.linear().transpose();
+        let to_world_record = |mut hit: HitRecord| {
+            hit.position = to_world.transform_point(&hit.position);
+            hit.normal = normal_matrix.transform(&hit.normal).unit();
+            hit.geometric_normal = normal_matrix.transform(&hit.geometric_normal).unit();
+            hit.dpdu = to_world.transform_vector(&hit.dpdu);
+            hit.dpdv = to_world.transform_vector(&hit.dpdv);
+            hit
+        };
+        return query(&object_ray, &to_world_record);
+    }
+
+    #[inline]
+    fn matrices(&self, time: float) -> (Mat4, Mat4) {
+        match &self.motion {
+            Motion::Static {
+                to_world,
+                to_object,
+            } => (*to_world, *to_object),
+            Motion::Animated { start, end } => {
+                let keyframe = start.lerp(end, time.clamp(0.0, 1.0));
+                (keyframe.to_world(), keyframe.to_object())
+            }
+        }
+    }
+
+    // Rotations sweep corners along arcs, so animated bounds are taken over
+    // several intermediate times rather than just the two keyframes.
+    fn motion_bounds(&self) -> Aabb {
+        const STEPS: usize = 32;
+        let object_box = self.object.bounding_box();
+        let steps = match self.motion {
+            Motion::Static { .. } => 0,
+            Motion::Animated { .. } => STEPS,
+        };
+        let mut bounds = Aabb::EMPTY;
+        for step in 0..=steps {
+            let (to_world, _) = self.matrices(step as float / STEPS as float);
+            for corner in object_box.corners() {
+                bounds = bounds.union_point(&to_world.transform_point(&corner));
+            }
+        }
+        return bounds;
+    }
+}
+
+impl<T: Hittable + ?Sized> Hittable for Transformed<T> {
+    fn hit(&self, ray: &Ray, t_interval: Interval) -> Option<HitRecord> {
+        self.in_object_space(ray, |object_ray, to_world| {
+            self.object.hit(object_ray, t_interval).map(to_world)
+        })
+    }
+
+    fn sample_medium(
+        &self,
+        ray: &Ray,
+        t_interval: Interval,
+        sampler: &mut SampleContext,
+    ) -> Option<HitRecord> {
+        self.in_object_space(ray, |object_ray, to_world| {
+            self.object
+                .sample_medium(object_ray, t_interval, sampler)
+                .map(to_world)
+        })
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bounding_box
+    }
+}
+
+impl<T: Solid + ?Sized> Solid for Transformed<T> {
+    fn spans(&self, ray: &Ray) -> Vec<Span> {
+        self.in_object_space(ray, |object_ray, to_world| {
+            self.object
+                .spans(object_ray)
+                .into_iter()
+                .map(|span| Span {
+                    enter: to_world(span.enter),
+                    exit: to_world(span.exit),
+                })
+                .collect()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::{csg::Csg, cuboid::Cuboid, material::Lambertian, sphere::Sphere};
+
+    #[test]
+    fn carves_rotated_cuboid() {
+        let material = Rc::new(Lambertian::new(Color::uniform(0.5)));
+        let cuboid = Cuboid::new(
+            Point3::uniform(-1.0),
+            Point3::uniform(1.0),
+            material.clone(),
+        );
+        let rotation = Mat4::rotation(&Vec3::Y, std::f32::consts::FRAC_PI_4);
+        let rotated = Transformed::new(Box::new(cuboid), rotation).unwrap();
+        let hole = Sphere::new(Point3::ZERO, 0.5, material);
+        let carved = Csg::difference(Box::new(rotated), Box::new(hole));
+
+        // Seen from above, the rotated box is the diamond |x| + |z| <= sqrt 2.
+        let z: float = 0.2;
+        let ray = Ray::new(Point3::new(-5.0, 0.0, z), Vec3::X);
+        let (edge, hole) = ((2.0 as float).sqrt() - z, (0.25 - z * z).sqrt());
+        let spans = carved.spans(&ray);
+        let boundaries: Vec<float> = spans
+            .iter()
+            .flat_map(|span| [span.enter.t, span.exit.t])
+            .collect();
+        let expected = [5.0 - edge, 5.0 - hole, 5.0 + hole, 5.0 + edge];
+        assert_eq!(boundaries.len(), expected.len());
+        for (t, expected) in boundaries.iter().zip(expected) {
+            assert!((t - expected).abs() < 1e-4, "{} != {}", t, expected);
+        }
+
+        let hit = carved
+            .hit(&ray, Interval::new(0.0, float::INFINITY))
+            .unwrap();
+        let normal = Vec3::new(-1.0, 0.0, 1.0).unit();
+        assert!((hit.normal - normal).lenght() < 1e-4);
+        assert!(hit.front_face);
+    }
+
+    #[test]
+    fn animated_scales_must_stay_invertible() {
+        let sphere = || -> Box<dyn Hittable> {
+            let material = Rc::new(Lambertian::new(Color::uniform(0.5)));
+            Box::new(Sphere::new(Point3::ZERO, 1.0, material))
+        };
+        let keyframe = |scale: Vec3| Keyframe::new(Vec3::ZERO, Quat::IDENTITY, scale);
+        let one = keyframe(Vec3::uniform(1.0));
+        let flipped = keyframe(Vec3::new(1.0, -1.0, 1.0));
+        let flat = keyframe(Vec3::new(1.0, 1.0, 0.0));
+
+        assert!(Transformed::new_animated(sphere(), one, keyframe(Vec3::uniform(2.0))).is_some());
+        assert!(Transformed::new_animated(sphere(), flipped, flipped).is_some());
+        assert!(Transformed::new_animated(sphere(), one, flipped).is_none());
+        assert!(Transformed::new_animated(sphere(), flat, one).is_none());
+    }
+}
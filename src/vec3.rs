@@ -4,7 +4,7 @@ use std::{
     ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign},
 };
 
-use crate::prelude::*;
+use crate::{prelude::*, sampler::SampleContext};
 
 #[derive(Clone, Copy, Default)]
 pub struct Vec3 {
@@ -48,39 +48,30 @@ impl Vec3 {
         Self::new(rand(min, max), rand(min, max), rand(min, max))
     }
 
+    // Maps a 2D sample to the disk directly rather than by rejection, so that
+    // stratified samples give stratified points.
     #[inline]
-    pub fn random_in_unit_sphere() -> Self {
-        loop {
-            let p = Self::random(-1.0, 1.0);
-            if p.lenght_squared() < 1.0 {
-                return p;
-            }
-        }
-    }
-
-    #[inline]
-    pub fn random_in_unit_disk() -> Self {
-        loop {
-            let p = Vec3::new(rand(-1.0, 1.0), rand(-1.0, 1.0), 0.0);
-            if p.lenght_squared() < 1.0 {
-                return p;
-            }
-        }
+    pub fn random_in_unit_disk(sampler: &mut SampleContext) -> Self {
+        let [u, v] = sampler.next_2d();
+        let (r, phi) = (u.sqrt(), 2.0 * PI * v);
+        Vec3::new(r * phi.cos(), r * phi.sin(), 0.0)
     }
 
     #[inline]
-    pub fn random_unit_vector() -> Self {
-        Self::random_in_unit_sphere().unit()
+    pub fn random_unit_vector(sampler: &mut SampleContext) -> Self {
+        let [u, v] = sampler.next_2d();
+        let z = 1.0 - 2.0 * u;
+        let r = (1.0 - z * z).max(0.0).sqrt();
+        let phi = 2.0 * PI * v;
+        Vec3::new(r * phi.cos(), r * phi.sin(), z)
     }
 
+    // Cosine weighted direction about +Z.
     #[inline]
-    pub fn random_on_hemisphere(normal: &Vec3) -> Self {
-        let vector = Self::random_unit_vector();
-        if vector.dot(&normal) > 0.0 {
-            vector
-        } else {
-            -vector
-        }
+    pub fn random_cosine_direction(sampler: &mut SampleContext) -> Self {
+        let [u, v] = sampler.next_2d();
+        let (r, phi) = (u.sqrt(), 2.0 * PI * v);
+        Vec3::new(r * phi.cos(), r * phi.sin(), (1.0 - r * r).max(0.0).sqrt())
     }
 
     #[inline]
@@ -166,10 +157,20 @@ impl Vec3 {
         Self::from_array(self.e.map(f))
     }
 
+    #[inline]
+    pub fn luminance(&self) -> float {
+        0.2126 * self.r() + 0.7152 * self.g() + 0.0722 * self.b()
+    }
+
     #[inline]
     pub fn gamma_corrected(self) -> Self {
         self.map(|c| c.sqrt())
     }
+
+    #[inline]
+    pub fn gamma_decoded(self) -> Self {
+        self.map(|c| c * c)
+    }
 }
 
 impl Display for Vec3 {
@@ -0,0 +1,307 @@
+use std::{
+    error::Error,
+    hash::{Hash, Hasher},
+    path::Path,
+    rc::Rc,
+};
+
+use crate::{
+    aabb::Aabb,
+    hittable::{HitRecord, Hittable},
+    interval::Interval,
+    material::Material,
+    medium::sample_henyey_greenstein,
+    prelude::*,
+    sampler::SampleContext,
+    spectrum::blackbody,
+};
+
+pub type VoxelResult<T> = Result<T, Box<dyn Error>>;
+
+// A dense grid of scalar values, such as density or temperature, with x
+// varying fastest, then y, then z.
+pub struct VoxelGrid {
+    size: [usize; 3],
+    values: Vec<float>,
+    max_value: float,
+}
+
+impl VoxelGrid {
+    pub fn new(size: [usize; 3], values: Vec<float>) -> Self {
+        assert!(
+            size.iter().all(|&n| n > 0),
+            "voxel grids must have at least one voxel along each axis"
+        );
+        assert_eq!(
+            values.len(),
+            size[0] * size[1] * size[2],
+            "voxel count must match size"
+        );
+        assert!(
+            values.iter().all(|&v| v >= 0.0),
+            "voxel values must not be negative"
+        );
+        let max_value = values.iter().copied().fold(0.0, float::max);
+        Self {
+            size,
+            values,
+            max_value,
+        }
+    }
+
+    // Fills the grid from a function of the voxel centers in [0, 1]^3.
+    pub fn from_fn(size: [usize; 3], f: impl Fn(&Point3) -> float) -> Self {
+        let [nx, ny, nz] = size;
+        let values = (0..nx * ny * nz)
+            .map(|i| {
+                let (x, y, z) = (i % nx, (i / nx) % ny, i / (nx * ny));
+                f(&Point3::new(
+                    (x as float + 0.5) / nx as float,
+                    (y as float + 0.5) / ny as float,
+                    (z as float + 0.5) / nz as float,
+                ))
+            })
+            .collect();
+        Self::new(size, values)
+    }
+
+    // Reads a grid file: an ASCII header line "VOXELS <nx> <ny> <nz>"
+    // followed by nx * ny * nz little endian 32 bit floats.
+    pub fn load(path: impl AsRef<Path>) -> VoxelResult<Self> {
+        let data = std::fs::read(path)?;
+        let header_end = data
+            .iter()
+            .position(|&b| b == b'\n')
+            .ok_or("missing voxel grid header")?;
+        let header = std::str::from_utf8(&data[..header_end])?;
+        let mut tokens = header.split_whitespace();
+        if tokens.next() != Some("VOXELS") {
+            return Err("not a voxel grid file".into());
+        }
+        let mut size = [0usize; 3];
+        for axis in &mut size {
+            *axis = tokens.next().ok_or("incomplete voxel grid size")?.parse()?;
+        }
+        if size.contains(&0) {
+            return Err("voxel grid size must not be zero".into());
+        }
+
+        let count = size[0]
+            .checked_mul(size[1])
+            .and_then(|count| count.checked_mul(size[2]))
+            .ok_or("voxel grid size is too large")?;
+        let bytes = count.checked_mul(4).ok_or("voxel grid size is too large")?;
+        let raster = &data[header_end + 1..];
+        if raster.len() < bytes {
+            return Err("voxel grid data is truncated".into());
+        }
+        let values: Vec<float> = raster
+            .chunks_exact(4)
+            .take(count)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]) as float)
+            .collect();
+        if !values.iter().all(|&v| v >= 0.0) {
+            return Err("voxel grid has negative values".into());
+        }
+        return Ok(Self::new(size, values));
+    }
+
+    #[inline]
+    pub fn max_value(&self) -> float {
+        self.max_value
+    }
+
+    fn voxel(&self, x: usize, y: usize, z: usize) -> float {
+        let [nx, ny, _] = self.size;
+        self.values[(z * ny + y) * nx + x]
+    }
+
+    // Trilinearly interpolates between voxel centers at `local` in [0, 1]^3,
+    // and is 0 outside of it.
+    pub fn sample(&self, local: &Point3) -> float {
+        if local.e.iter().any(|c| !(0.0..=1.0).contains(c)) {
+            return 0.0;
+        }
+
+        let mut lower = [0; 3];
+        let mut upper = [0; 3];
+        let mut weight = [0.0; 3];
+        for axis in 0..3 {
+            let n = self.size[axis];
+            let x = (local.e[axis] * n as float - 0.5).clamp(0.0, (n - 1) as float);
+            lower[axis] = x as usize;
+            upper[axis] = (lower[axis] + 1).min(n - 1);
+            weight[axis] = x - lower[axis] as float;
+        }
+
+        let lerp = |a: float, b: float, t: float| a + (b - a) * t;
+        let along_x = |y: usize, z: usize| {
+            lerp(
+                self.voxel(lower[0], y, z),
+                self.voxel(upper[0], y, z),
+                weight[0],
+            )
+        };
+        let along_y = |z: usize| lerp(along_x(lower[1], z), along_x(upper[1], z), weight[1]);
+        return lerp(along_y(lower[2]), along_y(upper[2]), weight[2]);
+    }
+}
+
+impl std::fmt::Debug for VoxelGrid {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("VoxelGrid")
+            .field("size", &self.size)
+            .field("max_value", &self.max_value)
+            .finish_non_exhaustive()
+    }
+}
+
+// Hashes every voxel, e.g. for the scene id of checkpoints.
+impl Hash for VoxelGrid {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.size.hash(state);
+        for value in &self.values {
+            value.to_bits().hash(state);
+        }
+    }
+}
+
+// Maps a world position inside `bounds` to [0, 1]^3.
+fn to_grid(bounds: &Aabb, position: &Point3) -> Point3 {
+    (position - bounds.min()) / (bounds.max() - bounds.min())
+}
+
+// A heterogeneous volume whose density is given by a voxel grid stretched
+// over `bounds`. Collisions are found with delta tracking against the grid's
+// maximum density, and the material decides how they scatter or emit.
+pub struct GridMedium {
+    bounds: Aabb,
+    density: Rc<VoxelGrid>,
+    density_scale: float,
+    material: Rc<dyn Material>,
+}
+
+impl GridMedium {
+    pub fn new(density: Rc<VoxelGrid>, bounds: Aabb, material: Rc<dyn Material>) -> Self {
+        Self {
+            bounds,
+            density,
+            density_scale: 1.0,
+            material,
+        }
+    }
+
+    // Multiplies the grid values to get the extinction coefficient.
+    #[inline]
+    pub fn with_density_scale(mut self, density_scale: float) -> Self {
+        self.density_scale = density_scale;
+        self
+    }
+
+    fn density_at(&self, position: &Point3) -> float {
+        self.density_scale * self.density.sample(&to_grid(&self.bounds, position))
+    }
+
+    fn majorant(&self) -> float {
+        self.density_scale * self.density.max_value()
+    }
+}
+
+impl Hittable for GridMedium {
+    fn hit(&self, _ray: &Ray, _t_interval: Interval) -> Option<HitRecord> {
+        None
+    }
+
+    fn sample_medium(
+        &self,
+        ray: &Ray,
+        t_interval: Interval,
+        sampler: &mut SampleContext,
+    ) -> Option<HitRecord> {
+        let majorant = self.majorant();
+        let span = self.bounds.hit(ray, t_interval)?;
+        if majorant <= 0.0 {
+            return None;
+        }
+
+        // Sample tentative collisions against the majorant, and accept each
+        // as real with the ratio of the local density to it.
+        let speed = ray.direction().lenght();
+        let mut t = span.min;
+        loop {
+            t -= (1.0 - sampler.next_1d()).ln() / (majorant * speed);
+            if t >= span.max {
+                return None;
+            }
+            let position = ray.at(t);
+            if sampler.next_1d() * majorant < self.density_at(&position) {
+                return Some(HitRecord::from_outward_normal(
+                    t,
+                    position,
+                    self.material.clone(),
+                    -ray.direction().unit(),
+                    ray,
+                ));
+            }
+        }
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bounds
+    }
+}
+
+// Hot gas: scatters with a Henyey-Greenstein phase function and emits
+// blackbody radiation given by a temperature grid in Kelvin over `bounds`.
+// Only the absorbed fraction of collisions, 1 - albedo, emits.
+#[derive(Debug)]
+pub struct Blackbody {
+    temperature: Rc<VoxelGrid>,
+    bounds: Aabb,
+    intensity: float,
+    albedo: Color,
+    g: float,
+}
+
+impl Blackbody {
+    pub fn new(temperature: Rc<VoxelGrid>, bounds: Aabb, intensity: float) -> Self {
+        Self {
+            temperature,
+            bounds,
+            intensity,
+            albedo: Color::uniform(0.5),
+            g: 0.0,
+        }
+    }
+
+    #[inline]
+    pub fn with_albedo(mut self, albedo: Color) -> Self {
+        self.albedo = albedo;
+        self
+    }
+
+    #[inline]
+    pub fn with_anisotropy(mut self, g: float) -> Self {
+        self.g = g.clamp(-0.99, 0.99);
+        self
+    }
+}
+
+impl Material for Blackbody {
+    fn scatter(
+        &self,
+        in_ray: &Ray,
+        hit_record: &HitRecord,
+        sampler: &mut SampleContext,
+    ) -> Option<(Color, Ray)> {
+        let direction = sample_henyey_greenstein(&in_ray.direction(), self.g, sampler);
+        return Some((self.albedo, in_ray.spawn(hit_record.position, direction)));
+    }
+
+    fn emitted(&self, _in_ray: &Ray, hit_record: &HitRecord) -> Color {
+        let temperature = self
+            .temperature
+            .sample(&to_grid(&self.bounds, &hit_record.position));
+        return self.intensity * (Color::uniform(1.0) - self.albedo) * blackbody(temperature);
+    }
+}
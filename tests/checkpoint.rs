@@ -0,0 +1,130 @@
+use std::{
+    hash::{Hash, Hasher},
+    path::PathBuf,
+    rc::Rc,
+};
+
+use rust_raytracer::{
+    camera::{CameraBuilder, SnapshotInterval},
+    checkpoint::StableHasher,
+    hittable_list::HittableList,
+    image::Image,
+    material::Lambertian,
+    prelude::*,
+    sphere::Sphere,
+    texture::ImageTexture,
+};
+
+// A textured sphere on the ground, with the texture's hash as the scene id.
+fn scene(texture: Image) -> (HittableList, u64) {
+    let mut hasher = StableHasher::default();
+    texture.hash(&mut hasher);
+    let scene_id = hasher.finish();
+
+    let mut world = HittableList::new();
+    let ground = Rc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+    world.add(Box::new(Sphere::new(
+        Point3::new(0.0, -100.5, -1.0),
+        100.0,
+        ground,
+    )));
+    let textured = Rc::new(Lambertian::from_texture(Rc::new(ImageTexture::new(
+        Rc::new(texture),
+    ))));
+    world.add(Box::new(Sphere::new(
+        Point3::new(0.0, 0.0, -1.0),
+        0.5,
+        textured,
+    )));
+    (world, scene_id)
+}
+
+fn texture() -> Image {
+    let pixels = vec![
+        Color::new(0.9, 0.1, 0.1),
+        Color::new(0.1, 0.9, 0.1),
+        Color::new(0.1, 0.1, 0.9),
+        Color::new(0.9, 0.9, 0.1),
+    ];
+    Image::new(2, 2, pixels)
+}
+
+fn camera() -> CameraBuilder {
+    CameraBuilder::default()
+        .with_look_from(Point3::ZERO)
+        .with_look_at(Point3::NEG_Z)
+        .with_image_width(16)
+        .with_samples_per_pixel(6)
+        .with_progressive(true)
+}
+
+fn checkpoint_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!(
+        "rust-raytracer-{}-{}.checkpoint",
+        name,
+        std::process::id()
+    ));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+fn same_pixels(a: &Image, b: &Image) -> bool {
+    [a.width(), a.height()] == [b.width(), b.height()]
+        && (0..a.height()).all(|j| (0..a.width()).all(|i| a.pixel(i, j).e == b.pixel(i, j).e))
+}
+
+#[test]
+fn resumed_render_matches_uninterrupted_render() {
+    let (world, scene_id) = scene(texture());
+    let path = checkpoint_path("resume");
+    let checkpointed = || camera().with_checkpoint(&path, SnapshotInterval::Passes(1), scene_id);
+
+    let passes = |camera: CameraBuilder, limit: usize| {
+        camera
+            .with_pass_limit(limit)
+            .build()
+            .render_image(&world)
+            .unwrap()
+    };
+
+    // Each run carries on from where the previous one stopped, rather than
+    // starting over.
+    let first_run = passes(checkpointed(), 2);
+    assert!(same_pixels(&first_run, &passes(camera(), 2)));
+    let second_run = passes(checkpointed(), 2);
+    assert!(same_pixels(&second_run, &passes(camera(), 4)));
+    assert!(!same_pixels(&second_run, &first_run));
+
+    let resumed = checkpointed().build().render_image(&world).unwrap();
+    let uninterrupted = camera().build().render_image(&world).unwrap();
+    let _ = std::fs::remove_file(&path);
+    assert!(same_pixels(&resumed, &uninterrupted));
+}
+
+#[test]
+fn checkpoint_of_modified_scene_is_refused() {
+    let (world, scene_id) = scene(texture());
+    let path = checkpoint_path("modified");
+    camera()
+        .with_checkpoint(&path, SnapshotInterval::Passes(1), scene_id)
+        .with_pass_limit(2)
+        .build()
+        .render_image(&world)
+        .unwrap();
+
+    let mut pixels: Vec<Color> = (0..2)
+        .flat_map(|j| (0..2).map(move |i| (i, j)))
+        .map(|(i, j)| texture().pixel(i, j))
+        .collect();
+    pixels[3] = Color::new(0.9, 0.9, 0.2);
+    let (world, modified_id) = scene(Image::new(2, 2, pixels));
+    assert_ne!(modified_id, scene_id);
+    let result = camera()
+        .with_checkpoint(&path, SnapshotInterval::Passes(1), modified_id)
+        .build()
+        .render_image(&world);
+    let _ = std::fs::remove_file(&path);
+
+    let error = result.expect_err("a modified scene resumed the checkpoint");
+    assert!(error.to_string().contains("different scene"), "{}", error);
+}
@@ -0,0 +1,152 @@
+use std::{
+    io::{BufReader, Cursor},
+    net::{TcpListener, TcpStream},
+    sync::mpsc,
+    thread,
+    time::Duration,
+};
+
+use rust_raytracer::{
+    camera::CameraBuilder,
+    distributed::{
+        coordinate, receive, send, work, Job, Message, DEFAULT_TIMEOUT, HEARTBEAT_INTERVAL,
+    },
+    hittable_list::HittableList,
+    image::Image,
+    prelude::*,
+    scene::random_spheres,
+};
+
+fn job() -> Job {
+    Job {
+        seed: 7,
+        image_width: 24,
+        samples_per_pixel: 2,
+        grid: [3, 2],
+    }
+}
+
+fn scene(job: &Job) -> (HittableList, CameraBuilder) {
+    random_spheres(job.seed)
+}
+
+fn same_pixels(a: &Image, b: &Image) -> bool {
+    [a.width(), a.height()] == [b.width(), b.height()]
+        && (0..a.height()).all(|j| (0..a.width()).all(|i| a.pixel(i, j).e == b.pixel(i, j).e))
+}
+
+#[test]
+fn messages_survive_a_round_trip() {
+    let pixels = vec![
+        Color::new(0.25, 1.5, -0.0),
+        Color::new(1e-7, 3.0, 0.5),
+        Color::new(0.0, 0.0, 42.0),
+        Color::new(0.1, 0.2, 0.3),
+        Color::new(7.0, 8.0, 9.0),
+        Color::new(0.0, 1.0, 0.0),
+    ];
+    let messages = [
+        Message::Job(job()),
+        Message::Tile([2, 1]),
+        Message::Pixels([8, 12], Image::new(3, 2, pixels.clone())),
+        Message::Alive,
+        Message::Quit,
+    ];
+    let mut data = Vec::new();
+    for message in &messages {
+        send(&mut data, message).unwrap();
+    }
+
+    let mut input = Cursor::new(data);
+    assert!(matches!(receive(&mut input, 6), Ok(Message::Job(job)) if job == self::job()));
+    assert!(matches!(receive(&mut input, 6), Ok(Message::Tile([2, 1]))));
+    match receive(&mut input, 6) {
+        Ok(Message::Pixels([8, 12], image)) => {
+            assert!(same_pixels(&image, &Image::new(3, 2, pixels)));
+        }
+        message => panic!("expected pixels, got {:?}", message),
+    }
+    assert!(matches!(receive(&mut input, 6), Ok(Message::Alive)));
+    assert!(matches!(receive(&mut input, 6), Ok(Message::Quit)));
+    assert!(receive(&mut input, 6).is_err());
+}
+
+#[test]
+fn truncated_pixels_are_an_error() {
+    let mut data = Vec::new();
+    let image = Image::new(2, 2, vec![Color::new(1.0, 2.0, 3.0); 4]);
+    send(&mut data, &Message::Pixels([0, 0], image)).unwrap();
+    data.truncate(data.len() - 1);
+    assert!(receive(&mut Cursor::new(data), 4).is_err());
+}
+
+#[test]
+fn oversized_pixels_are_refused_before_reading() {
+    let mut data = Vec::new();
+    let image = Image::new(2, 2, vec![Color::new(1.0, 2.0, 3.0); 4]);
+    send(&mut data, &Message::Pixels([0, 0], image)).unwrap();
+    assert!(receive(&mut Cursor::new(data), 3).is_err());
+
+    let overflowing = format!("PIXELS 0 0 {} 2\n", usize::MAX);
+    assert!(receive(&mut Cursor::new(overflowing.into_bytes()), usize::MAX).is_err());
+}
+
+// Renders the job with a worker that takes a tile and hands its connection
+// to `fail`, and two workers that do their job. The good workers only start
+// once the failing one has its tile, so that it is sure to get one.
+fn render_with_failing_worker(
+    timeout: Duration,
+    fail: impl FnOnce(BufReader<TcpStream>) + Send + 'static,
+) -> Image {
+    let job = job();
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let coordinator = thread::spawn(move || coordinate(listener, job, scene, timeout).unwrap());
+
+    let (took_tile, tile_taken) = mpsc::channel();
+    let failing = thread::spawn(move || {
+        let mut reader = BufReader::new(TcpStream::connect(address).unwrap());
+        assert!(matches!(receive(&mut reader, 0), Ok(Message::Job(_))));
+        assert!(matches!(receive(&mut reader, 0), Ok(Message::Tile(_))));
+        took_tile.send(()).unwrap();
+        fail(reader);
+    });
+    tile_taken.recv().unwrap();
+
+    let workers: Vec<_> = (0..2)
+        .map(|_| thread::spawn(move || work(TcpStream::connect(address).unwrap(), scene).unwrap()))
+        .collect();
+    let frame = coordinator.join().unwrap();
+    for worker in workers {
+        worker.join().unwrap();
+    }
+    failing.join().unwrap();
+    frame
+}
+
+fn single_process_render() -> Image {
+    let (world, camera) = job().scene(scene);
+    camera.build().render_image(&world).unwrap()
+}
+
+#[test]
+fn tiles_of_a_disconnected_worker_are_rendered_by_the_others() {
+    let frame = render_with_failing_worker(DEFAULT_TIMEOUT, drop);
+    assert!(same_pixels(&frame, &single_process_render()));
+}
+
+#[test]
+fn tiles_of_a_silent_worker_are_rendered_by_the_others() {
+    // Stays connected without a word until the coordinator gives up on it.
+    let timeout = HEARTBEAT_INTERVAL + Duration::from_secs(1);
+    let frame = render_with_failing_worker(timeout, |mut reader| {
+        assert!(receive(&mut reader, 0).is_err());
+    });
+    assert!(same_pixels(&frame, &single_process_render()));
+}
+
+#[test]
+fn timeouts_within_the_heartbeat_interval_are_refused() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    assert!(coordinate(listener, job(), scene, HEARTBEAT_INTERVAL).is_err());
+}